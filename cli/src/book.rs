@@ -0,0 +1,106 @@
+use crate::exit_with;
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use std::{path::PathBuf, sync::Arc};
+
+#[derive(Args, Debug)]
+pub struct BookArgs {
+    #[command(subcommand)]
+    command: BookCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum BookCommand {
+    /// Build an opening book from PDN games or, without games, from deep searches
+    Build(BuildArgs),
+}
+
+#[derive(Args, Debug)]
+struct BuildArgs {
+    /// The PDN games to take the moves from
+    #[arg(long)]
+    pdn: Option<PathBuf>,
+    /// The file to write the book to
+    #[arg(short, long, default_value = "book.txt")]
+    output: PathBuf,
+    /// The number of plies from the start of the game covered by the book
+    #[arg(short, long, default_value_t = 8)]
+    plies: usize,
+    /// Only keep moves played in at least this many games
+    #[arg(long, default_value_t = 1)]
+    min_weight: u32,
+    /// The depth of the searches
+    #[arg(short, long, default_value_t = 10)]
+    depth: u8,
+    /// The number of best moves considered in every searched position
+    #[arg(long, default_value_t = 2)]
+    width: usize,
+    /// The evaluation function of the searches [v1, v2, v3]
+    #[arg(short, long, default_value = "v3")]
+    eval: String,
+}
+
+pub fn run(args: BookArgs) {
+    match args.command {
+        BookCommand::Build(build_args) => build(build_args),
+    }
+}
+
+fn build(args: BuildArgs) {
+    let book = match &args.pdn {
+        Some(path) => {
+            let games = logic::load_pdn(path).unwrap_or_else(|err| exit_with(err));
+            println!("{}", format!("Games: {}", games.len()).dimmed());
+            let mut book = logic::OpeningBook::from_games(&games, args.plies);
+            book.prune(args.min_weight);
+            book
+        }
+        None => {
+            let eval = logic::evaluator(&args.eval).unwrap_or_else(|| {
+                exit_with(format!(
+                    "Unknown evaluation function '{}'. Available: {}",
+                    args.eval,
+                    logic::EVALUATORS.join(", ")
+                ))
+            });
+            let limits = logic::SearchLimits::depth(args.depth.clamp(1, logic::MAX_DEPTH));
+            let mut analyser = logic::MinimaxPlayer::with_evaluator(limits, Arc::clone(&eval));
+            let mut book = logic::OpeningBook::new();
+            book.add_searched(
+                &mut analyser,
+                &logic::Position::new(),
+                args.plies,
+                args.width,
+                |position, result| {
+                    println!(
+                        "{}",
+                        format!(
+                            "{} | {}",
+                            position.to_fen(logic::FenFormat::Pdn),
+                            result
+                                .lines
+                                .iter()
+                                .map(|line| format!("{} {}", line.pv[0], line.score))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )
+                        .dimmed()
+                    );
+                },
+            );
+            book
+        }
+    };
+
+    book.save(&args.output).unwrap_or_else(|err| exit_with(err));
+    println!(
+        "{}",
+        format!(
+            "Opening book with {} positions written to {}",
+            book.len(),
+            args.output.display()
+        )
+        .green()
+        .bold()
+    );
+}
@@ -0,0 +1,101 @@
+use crate::exit_with;
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use std::{path::PathBuf, time::Instant};
+
+#[derive(Args, Debug)]
+pub struct EgdbArgs {
+    #[command(subcommand)]
+    command: EgdbCommand,
+}
+
+#[derive(Subcommand, Debug)]
+enum EgdbCommand {
+    /// Build the endgame tables of every material up to a number of pieces
+    Generate {
+        /// The directory to write the tables to
+        dir: PathBuf,
+        /// The maximum number of pieces on the board
+        #[arg(short, long, default_value_t = 4)]
+        pieces: u8,
+    },
+    /// Compare the endgame tables with a brute-force search
+    Validate {
+        /// The directory of the tables
+        dir: PathBuf,
+        /// The maximum number of pieces of the compared positions
+        #[arg(short, long, default_value_t = 3)]
+        pieces: u8,
+        /// The depth of the brute-force search in plies
+        #[arg(short, long, default_value_t = 20)]
+        depth: u8,
+    },
+}
+
+pub fn run(args: EgdbArgs) {
+    match args.command {
+        EgdbCommand::Generate { dir, pieces } => generate(dir, pieces),
+        EgdbCommand::Validate { dir, pieces, depth } => validate(dir, pieces, depth),
+    }
+}
+
+fn generate(dir: PathBuf, pieces: u8) {
+    let start = Instant::now();
+    logic::EndgameDatabase::generate(&dir, pieces, |table| {
+        let stats = table.stats();
+        println!(
+            "{}",
+            format!(
+                "{} | wins {} | losses {} | draws {} | longest {} plies | {:.1}s",
+                table.material(),
+                stats.wins,
+                stats.losses,
+                stats.draws,
+                stats.longest,
+                start.elapsed().as_secs_f64()
+            )
+            .dimmed()
+        );
+    })
+    .unwrap_or_else(|err| exit_with(err));
+    println!(
+        "{}",
+        format!("Endgame tables written to {}", dir.display())
+            .green()
+            .bold()
+    );
+}
+
+fn validate(dir: PathBuf, pieces: u8, depth: u8) {
+    let database = logic::EndgameDatabase::open(&dir).unwrap_or_else(|err| exit_with(err));
+    let report = database.validate(pieces, depth, |material| {
+        println!("{}", format!("{} checked", material).dimmed());
+    });
+
+    for mismatch in &report.mismatches {
+        println!(
+            "{}",
+            format!(
+                "{} | database {:?} | search {:?}",
+                mismatch.position.to_fen(logic::FenFormat::Pdn),
+                mismatch.expected,
+                mismatch.found
+            )
+            .red()
+        );
+    }
+    if report.mismatches.is_empty() {
+        println!(
+            "{}",
+            format!("All {} positions match", report.positions)
+                .green()
+                .bold()
+        );
+    } else {
+        exit_with(format!(
+            "{} of {} positions do not match",
+            report.mismatches.len(),
+            report.positions
+        ));
+    }
+}
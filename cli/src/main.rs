@@ -1,43 +1,145 @@
-use clap::Parser;
+mod book;
+mod egdb;
+mod perft;
+mod tune;
+
+use clap::{Parser, Subcommand};
 use colored::Colorize;
+use std::{fmt::Display, path::PathBuf, process, sync::Arc, time::Duration};
 
+/// Analyses a position when called without a command.
 #[derive(Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
-    /// The depth of the minimax algorithm
-    #[arg(short, long, default_value_t = 7)]
-    depth: u8,
-    /// The evaluation function to use [version 1, 2...]
-    #[arg(short, long, default_value_t = 2)]
-    eval: u8,
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// The maximum depth of the minimax algorithm
+    /// [default: 7, unlimited with --time or --nodes]
+    #[arg(verbatim_doc_comment, short, long)]
+    depth: Option<u8>,
+    /// Stop the search after this many milliseconds
+    #[arg(short, long)]
+    time: Option<u64>,
+    /// Stop the search after this many nodes
+    #[arg(short, long)]
+    nodes: Option<u64>,
+    /// The evaluation function to use [v1, v2, v3]
+    #[arg(short, long, default_value = "v2")]
+    eval: String,
+    /// Use the v3 evaluation with the weights of this file
+    #[arg(short, long, conflicts_with = "eval")]
+    weights: Option<PathBuf>,
+    /// The number of best moves to show with their lines
+    #[arg(short, long, default_value_t = 1)]
+    multi_pv: usize,
+    /// The size of the transposition table in MB
+    #[arg(long, default_value_t = logic::DEFAULT_TT_SIZE_MB)]
+    hash: usize,
+    /// Show the contributions of the evaluation features next to the board
+    #[arg(short = 'x', long)]
+    explain: bool,
+    /// The number of threads searching in parallel
+    #[arg(long, default_value_t = 1)]
+    threads: usize,
+    /// The directory of the endgame tables to probe during the search
+    #[arg(long)]
+    egdb: Option<PathBuf>,
+    /// The opening book to show the moves of the position from
+    #[arg(long)]
+    book: Option<PathBuf>,
     /// The board to evaluate in FEN notation
     /// 'm': black Man
     /// 'M': white Man
     /// 'k': black King
     /// 'K': white King
     /// '/': new row
-    /// '1-9': n empty squares
-    /// Example Starting Board: 1m1m1m1m/m1m1m1m1/1m1m1m1m/8/8/M1M1M1M1/1M1M1M1M/M1M1M1M1
-    #[arg(verbatim_doc_comment, short, long)]
-    fen: String,
+    /// '1-8': n empty squares
+    /// 'W'/'B': optional side to move after a space [default: W]
+    /// Example Starting Board: 1m1m1m1m/m1m1m1m1/1m1m1m1m/8/8/M1M1M1M1/1M1M1M1M/M1M1M1M1 W
+    /// The standard PDN FEN is accepted as well
+    /// Example Starting Board: W:W21-32:B1-12
+    #[arg(verbatim_doc_comment, short, long, required = true)]
+    fen: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Fit the weights of the v3 evaluation to positions labelled with game results
+    Tune(tune::TuneArgs),
+    /// Generate or validate endgame tables
+    Egdb(egdb::EgdbArgs),
+    /// Build opening books
+    Book(book::BookArgs),
+    /// Count the leaves of the move tree to check and benchmark the move generation
+    Perft(perft::PerftArgs),
 }
 
 fn main() {
     let args = Cli::parse();
+    match args.command {
+        Some(Command::Tune(tune_args)) => tune::run(tune_args),
+        Some(Command::Egdb(egdb_args)) => egdb::run(egdb_args),
+        Some(Command::Book(book_args)) => book::run(book_args),
+        Some(Command::Perft(perft_args)) => perft::run(perft_args),
+        None => analyse(args),
+    }
+}
 
-    let board = logic::Board::from_fen(&args.fen).unwrap();
-    let mut analyser = logic::MinimaxPlayer::new(
-        args.depth,
-        match args.eval {
-            1 => logic::v1,
-            2 => logic::v2,
-            _ => panic!("Invalid evaluation function"),
-        },
-    );
+fn analyse(args: Cli) {
+    let fen = args.fen.unwrap_or_default();
+    let position = logic::Position::from_fen(&fen).unwrap_or_else(|err| exit_with(err));
+    let default_depth = if args.time.is_some() || args.nodes.is_some() {
+        logic::MAX_DEPTH
+    } else {
+        7
+    };
+    let limits = logic::SearchLimits {
+        depth: args
+            .depth
+            .unwrap_or(default_depth)
+            .clamp(1, logic::MAX_DEPTH),
+        time: args.time.map(Duration::from_millis),
+        nodes: args.nodes,
+    };
+    let eval: Arc<dyn logic::Evaluator> = match &args.weights {
+        Some(path) => Arc::new(logic::V3::from_file(path).unwrap_or_else(|err| exit_with(err))),
+        None => logic::evaluator(&args.eval).unwrap_or_else(|| {
+            exit_with(format!(
+                "Unknown evaluation function '{}'. Available: {}",
+                args.eval,
+                logic::EVALUATORS.join(", ")
+            ))
+        }),
+    };
+    let eval_name = eval.name().to_string();
+    let explanation = args.explain.then(|| eval.explain(&position));
+    let mut analyser = logic::MinimaxPlayer::with_evaluator(limits, eval);
 
-    // TODO: Make current player [W/B] a parameter ... in FEN?
+    analyser.set_transposition_table_size(args.hash);
+    analyser.set_multi_pv(args.multi_pv);
+    if let Some(dir) = &args.egdb {
+        let database = logic::EndgameDatabase::open(dir).unwrap_or_else(|err| exit_with(err));
+        analyser.set_endgame_database(Arc::new(database));
+    }
+    analyser.set_threads(args.threads);
+    analyser.set_info_callback(|result| {
+        println!(
+            "{}",
+            format!(
+                "depth {} | score {} | nodes {} | pv {}",
+                result.depth,
+                format_score(result.score),
+                result.nodes,
+                format_line(&result.pv)
+            )
+            .dimmed()
+        );
+    });
 
-    let w_1 = 40 - args.depth.to_string().len();
-    let w_2 = 33 - args.eval.to_string().len();
+    let w_1 = 40 - limits.depth.to_string().len();
+    let w_2 = 33 - eval_name.len();
+    let turn_name = if position.turn() { "White" } else { "Black" };
+    let w_3 = 33 - turn_name.len();
 
     println!(
         "{}",
@@ -56,13 +158,19 @@ fn main() {
     println!(
         "{}Depth: {}{:>w_1$}",
         "| ".dimmed(),
-        args.depth.to_string().bold().cyan(),
+        limits.depth.to_string().bold().cyan(),
         "|".dimmed()
     );
     println!(
         "{}Eval Version: {}{:>w_2$}",
         "| ".dimmed(),
-        args.eval.to_string().bold().cyan(),
+        eval_name.bold().cyan(),
+        "|".dimmed()
+    );
+    println!(
+        "{}Side to move: {}{:>w_3$}",
+        "| ".dimmed(),
+        turn_name.bold().cyan(),
         "|".dimmed()
     );
     println!(
@@ -70,10 +178,137 @@ fn main() {
         "+-----------------------------------------------+".dimmed()
     );
     println!("\n{}\n", "Board:".bold().cyan());
-    println!("{}\n", board);
-    analyser.analyse(&board);
+    match &explanation {
+        Some(explanation) => print_explained_board(&position, explanation),
+        None => println!("{}\n", position.board()),
+    }
+    if let Some(path) = &args.book {
+        let book = logic::OpeningBook::load(path).unwrap_or_else(|err| exit_with(err));
+        let book_moves = book
+            .moves(&position)
+            .iter()
+            .map(|(p_move, weight)| format!("{} ({})", p_move, weight))
+            .collect::<Vec<_>>();
+        if !book_moves.is_empty() {
+            println!(
+                "{}\n",
+                format!("Book moves: {}", book_moves.join(", ")).cyan()
+            );
+        }
+    }
+    let result = analyser.analyse(&position);
+    println!();
+    // Only the moves with a line have exact values, the others are upper bounds
+    for (i, (p_move, value)) in result.root_moves.iter().enumerate() {
+        let value = if i < result.lines.len() {
+            format_score(*value)
+        } else {
+            format!("<= {}", format_score(*value))
+        };
+        println!("{}", format!("{} | {}", p_move, value.dimmed()).white());
+    }
+    if let Some(best_move) = &result.best_move {
+        println!(
+            "{}",
+            format!(
+                "Best move: {} | {} (depth {})",
+                best_move,
+                format_score(result.score),
+                result.depth
+            )
+            .green()
+            .bold()
+        );
+        for (i, line) in result.lines.iter().enumerate() {
+            println!(
+                "{}",
+                format!(
+                    "Line {}: {} | {}",
+                    i + 1,
+                    format_line(&line.pv),
+                    format_score(line.score)
+                )
+                .green()
+            );
+        }
+    }
+    println!(
+        "{}",
+        format!(
+            "Nodes: {} | {} nodes/s | TT hits: {:.1}% | First move cutoffs: {:.1}% | EGDB hits: {} | Time: {:.3}s",
+            result.nodes,
+            result.nodes_per_second,
+            result.tt_hit_rate * 100.,
+            result.first_move_cutoff_rate * 100.,
+            result.egdb_hits,
+            result.elapsed.as_secs_f64()
+        )
+        .dimmed()
+    );
     println!(
         "{}",
         "+-----------------------------------------------+".dimmed()
     );
 }
+
+/// Prints the board with a table of the evaluation features on its right.
+fn print_explained_board(position: &logic::Position, explanation: &[logic::Contribution]) {
+    let board = position.board().to_string();
+    let mut table = vec![format!("{:<20}{:>7}{:>9}", "Feature", "Value", "Score")
+        .bold()
+        .to_string()];
+    for contribution in explanation {
+        let value = contribution
+            .value
+            .map_or(String::new(), |value| value.to_string());
+        table.push(format!(
+            "{:<20}{:>7}{:>9}",
+            contribution.name,
+            value,
+            format_score(contribution.score)
+        ));
+    }
+    let total = explanation
+        .iter()
+        .map(|contribution| contribution.score)
+        .sum();
+    table.push(
+        format!("{:<20}{:>7}{:>9}", "total", "", format_score(total))
+            .bold()
+            .to_string(),
+    );
+
+    let board_lines = board.lines().collect::<Vec<_>>();
+    let width = board_lines.iter().map(|line| line.len()).max().unwrap_or(0);
+    for i in 0..board_lines.len().max(table.len()) {
+        let line = format!(
+            "{:<width$}    {}",
+            board_lines.get(i).unwrap_or(&""),
+            table.get(i).map_or("", String::as_str)
+        );
+        println!("{}", line.trim_end());
+    }
+    println!();
+}
+
+/// Prints the error and exits with a failure code.
+fn exit_with(err: impl Display) -> ! {
+    eprintln!("{}", err);
+    process::exit(1);
+}
+
+fn format_line(line: &[logic::Move]) -> String {
+    line.iter()
+        .map(|p_move| p_move.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Formats a score in pieces, or the number of moves until the game ends for a won position.
+fn format_score(score: logic::Score) -> String {
+    match logic::win_distance(score) {
+        Some(logic::WinDistance::Win(plies)) => format!("win in {}", plies.div_ceil(2)),
+        Some(logic::WinDistance::Loss(plies)) => format!("loss in {}", plies.div_ceil(2)),
+        None => format!("{:+.2}", score as f32 / logic::MAN_VALUE as f32),
+    }
+}
@@ -0,0 +1,63 @@
+use crate::exit_with;
+use clap::Args;
+use colored::Colorize;
+use std::time::Instant;
+
+#[derive(Args, Debug)]
+pub struct PerftArgs {
+    /// The number of plies to count the move tree to
+    #[arg(short, long, default_value_t = 8)]
+    depth: u8,
+    /// The position to start from in FEN notation
+    #[arg(short, long, default_value = "W:W21-32:B1-12")]
+    fen: String,
+    /// Show the count below every move of the position at the final depth
+    #[arg(long)]
+    divide: bool,
+}
+
+pub fn run(args: PerftArgs) {
+    let mut position = logic::Position::from_fen(&args.fen).unwrap_or_else(|err| exit_with(err));
+    println!("{}\n", position.board());
+
+    let start = Instant::now();
+    for depth in 1..=args.depth {
+        let depth_start = Instant::now();
+        let nodes = position.perft(depth);
+        let elapsed = depth_start.elapsed().as_secs_f64();
+        println!(
+            "Depth {:>2} | nodes {:>12} | {:>8.3}s | {:>6.2} Mnodes/s",
+            depth,
+            nodes,
+            elapsed,
+            nodes as f64 / elapsed.max(1e-9) / 1e6
+        );
+    }
+
+    if args.divide && args.depth > 0 {
+        println!();
+        let divide = position.divide(args.depth);
+        for (p_move, nodes) in &divide {
+            println!(
+                "{}",
+                format!("{:<16} {}", p_move.to_string(), nodes).dimmed()
+            );
+        }
+        println!(
+            "{}",
+            format!(
+                "{} moves, {} nodes",
+                divide.len(),
+                divide.iter().map(|(_, nodes)| nodes).sum::<u64>()
+            )
+            .bold()
+        );
+    }
+
+    println!(
+        "{}",
+        format!("Finished in {:.3}s", start.elapsed().as_secs_f64())
+            .green()
+            .bold()
+    );
+}
@@ -0,0 +1,67 @@
+use crate::exit_with;
+use clap::Args;
+use colored::Colorize;
+use std::path::PathBuf;
+
+#[derive(Args, Debug)]
+pub struct TuneArgs {
+    /// The labelled positions, one per line: a FEN followed by the result of the game
+    /// from white's point of view (1-0, 0-1 or 1/2-1/2)
+    /// Example: W:W21-32:B1-12 1/2-1/2
+    #[arg(verbatim_doc_comment)]
+    positions: PathBuf,
+    /// The file to write the tuned weights to
+    #[arg(short, long, default_value = "weights.txt")]
+    output: PathBuf,
+    /// The weights to start from [default: the built-in v3 weights]
+    #[arg(short, long)]
+    weights: Option<PathBuf>,
+    /// The maximum number of passes over all weights
+    #[arg(short, long, default_value_t = 100)]
+    iterations: usize,
+}
+
+pub fn run(args: TuneArgs) {
+    let positions =
+        logic::load_training_positions(&args.positions).unwrap_or_else(|err| exit_with(err));
+    let weights = match &args.weights {
+        Some(path) => logic::Weights::load(path).unwrap_or_else(|err| exit_with(err)),
+        None => logic::Weights::default(),
+    };
+
+    let mut tuner = logic::Tuner::new(&positions);
+    if tuner.sample_count() == 0 {
+        exit_with("No positions to tune with. Positions with pending captures are skipped.");
+    }
+    println!(
+        "{}",
+        format!(
+            "Positions: {} | skipped: {}",
+            tuner.sample_count(),
+            tuner.skipped()
+        )
+        .dimmed()
+    );
+    let error = tuner.fit_scale(&weights);
+    println!(
+        "{}",
+        format!("Scale: {:.6} | error: {:.6}", tuner.scale(), error).dimmed()
+    );
+
+    let weights = tuner.tune(weights, args.iterations, |iteration, error| {
+        println!(
+            "{}",
+            format!("iteration {} | error {:.6}", iteration, error).dimmed()
+        );
+    });
+    println!("\n{}", weights);
+    weights
+        .save(&args.output)
+        .unwrap_or_else(|err| exit_with(err));
+    println!(
+        "{}",
+        format!("Weights written to {}", args.output.display())
+            .green()
+            .bold()
+    );
+}
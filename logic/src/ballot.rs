@@ -0,0 +1,219 @@
+use crate::{
+    board::{Board, Move},
+    game::{Game, GameState},
+    pdn::{find_move, NotationError},
+    piece,
+    player::Player,
+    position::Position,
+};
+use std::{
+    collections::HashSet,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::Path,
+};
+
+/// Reasons for a ballot or a deck of ballots to be rejected. Lines are counted from 1.
+#[derive(Debug)]
+pub enum BallotError {
+    Io(io::Error),
+    /// The ballot does not have three moves.
+    MoveCount(usize),
+    /// A move is not legal in the position it is played in.
+    IllegalMove(String),
+    /// A shortened capture matches more than one capture route.
+    AmbiguousMove(String),
+    /// A line of a deck is not a valid ballot.
+    InvalidLine {
+        line: usize,
+        error: Box<BallotError>,
+    },
+    /// A ballot is listed twice in a deck.
+    Duplicate {
+        line: usize,
+    },
+}
+
+impl Display for BallotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BallotError::Io(err) => write!(f, "Could not read the ballot deck. {}", err),
+            BallotError::MoveCount(count) => {
+                write!(f, "A ballot has three moves, found {}.", count)
+            }
+            BallotError::IllegalMove(notation) => {
+                write!(f, "Illegal move '{}' in the ballot.", notation)
+            }
+            BallotError::AmbiguousMove(notation) => {
+                write!(f, "Ambiguous move '{}' in the ballot.", notation)
+            }
+            BallotError::InvalidLine { line, error } => {
+                write!(f, "Invalid ballot in line {}. {}", line, error)
+            }
+            BallotError::Duplicate { line } => {
+                write!(f, "The ballot in line {} is listed twice.", line)
+            }
+        }
+    }
+}
+
+impl Error for BallotError {}
+
+impl From<io::Error> for BallotError {
+    fn from(err: io::Error) -> Self {
+        BallotError::Io(err)
+    }
+}
+
+/// The three opening moves of a game, starting from the initial board with black to move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ballot {
+    moves: Vec<Move>,
+}
+
+impl Ballot {
+    /* --------------| Constructors |-------------- */
+    /// Parses three moves in standard notation, e.g. `11-15 23-19 8-11`.
+    pub fn parse(notation: &str) -> Result<Self, BallotError> {
+        let notations = notation.split_whitespace().collect::<Vec<_>>();
+        if notations.len() != 3 {
+            return Err(BallotError::MoveCount(notations.len()));
+        }
+
+        let mut position = Self::start();
+        let mut moves = Vec::with_capacity(3);
+        for notation in notations {
+            let p_move = find_move(&position, notation).map_err(|err| match err {
+                NotationError::Illegal => BallotError::IllegalMove(notation.to_string()),
+                NotationError::Ambiguous => BallotError::AmbiguousMove(notation.to_string()),
+            })?;
+            position.move_piece(&p_move);
+            moves.push(p_move);
+        }
+        Ok(Self { moves })
+    }
+
+    /// Parses a deck of ballots, one ballot per line like `11-15 23-19 8-11`. '#' starts a
+    /// comment. The ballots keep the order of the deck, so they can be referred to by number.
+    pub fn parse_deck(text: &str) -> Result<Vec<Self>, BallotError> {
+        let mut ballots = Vec::new();
+        let mut seen = HashSet::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let ballot = Self::parse(line).map_err(|error| BallotError::InvalidLine {
+                line: i + 1,
+                error: Box::new(error),
+            })?;
+            if !seen.insert(ballot.to_string()) {
+                return Err(BallotError::Duplicate { line: i + 1 });
+            }
+            ballots.push(ballot);
+        }
+        Ok(ballots)
+    }
+
+    pub fn load_deck(path: impl AsRef<Path>) -> Result<Vec<Self>, BallotError> {
+        Self::parse_deck(&fs::read_to_string(path)?)
+    }
+
+    /* --------------| Getters |-------------- */
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /* --------------| Methods |-------------- */
+    /// The position after the three moves, with white to move.
+    pub fn position(&self) -> Position {
+        let mut position = Self::start();
+        for p_move in &self.moves {
+            position.move_piece(p_move);
+        }
+        position
+    }
+
+    pub fn board(&self) -> Board {
+        self.position().board().clone()
+    }
+
+    /* --------------| Private methods |-------------- */
+    fn start() -> Position {
+        Position::from_board(Board::new(), piece::BLACK)
+    }
+}
+
+impl Display for Ballot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let moves = self
+            .moves
+            .iter()
+            .map(|p_move| p_move.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "{}", moves.join(" "))
+    }
+}
+
+/// The games of a match from the point of view of the first player.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MatchResult {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl MatchResult {
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    /// Points per game of the first player, counting a draw as half a win.
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.0;
+        }
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+}
+
+impl Display for MatchResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "+{} -{} ={}", self.wins, self.losses, self.draws)
+    }
+}
+
+/// Plays two games per ballot, so both players play each side of every opening: the first game
+/// with `player_1` as black, the second with `player_1` as white. The factories create fresh
+/// players for every game. `progress` receives each ballot with the results of its two games.
+pub fn play_match(
+    ballots: &[Ballot],
+    mut player_1: impl FnMut() -> Box<dyn Player>,
+    mut player_2: impl FnMut() -> Box<dyn Player>,
+    mut progress: impl FnMut(&Ballot, &MatchResult),
+) -> MatchResult {
+    let mut result = MatchResult::default();
+    for ballot in ballots {
+        let mut ballot_result = MatchResult::default();
+        for player_1_color in [piece::BLACK, piece::WHITE] {
+            let mut game = if player_1_color == piece::WHITE {
+                Game::from_ballot(ballot, player_1(), player_2())
+            } else {
+                Game::from_ballot(ballot, player_2(), player_1())
+            };
+            game.play();
+            match game.state {
+                GameState::Win(color) if color == player_1_color => ballot_result.wins += 1,
+                GameState::Win(_) => ballot_result.losses += 1,
+                _ => ballot_result.draws += 1,
+            }
+        }
+        progress(ballot, &ballot_result);
+        result.wins += ballot_result.wins;
+        result.losses += ballot_result.losses;
+        result.draws += ballot_result.draws;
+    }
+    result
+}
@@ -1,10 +1,12 @@
 use crate::{
+    fen::id_to_square,
     piece::{self, Piece},
-    return_if,
+    return_if, zobrist,
 };
 
-use super::piece::{PieceColor, LEFT_DISTANCE, RIGHT_DISTANCE};
+use super::piece::PieceColor;
 use std::{
+    error::Error,
     fmt::{Display, Formatter, Result},
     hash::{Hash, Hasher},
     result,
@@ -14,31 +16,116 @@ pub type Bitboard = u64;
 const EMPTY: Bitboard = 0;
 const DEFAULT_WHITE: Bitboard = 0x0000_0000_0055_AA55;
 const DEFAULT_BLACK: Bitboard = 0xAA55_AA00_0000_0000;
+pub(crate) const DARK_SQUARES: Bitboard = 0xAA55_AA55_AA55_AA55;
+const WHITE_CROWNING_ROW: Bitboard = 0xFF00_0000_0000_0000;
+const BLACK_CROWNING_ROW: Bitboard = 0x0000_0000_0000_00FF;
 // const DEFAULT_WHITE: Bitboard = 0x0000_0000_0055_AA55;
 // const DEFAULT_BLACK: Bitboard = 0x0000_0000_AA00_0000;
 
-type MoveType = bool;
-const JUMP: MoveType = true;
-const STANDARD: MoveType = false;
+/// A complete move for one turn.
+/// A capture sequence is a single move containing every landing square and every captured square.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Move {
+    /// Every square the piece visits, starting with the square it moves from.
+    pub path: Vec<u8>,
 
-/// All possible moves for a piece.
-#[derive(Debug)]
-pub struct PossibleMoves {
-    pub from: u8,
-    pub to: Vec<u8>,
+    /// Every captured square, in the order they are jumped.
+    pub captures: Vec<u8>,
+
+    /// Bitboard of the captured squares that held a king. Needed to unmake the move.
+    pub captured_kings: Bitboard,
+
+    /// Whether the moving man gets crowned.
+    pub promotion: bool,
+}
+
+impl Move {
+    #[inline]
+    pub fn from(&self) -> u8 {
+        self.path[0]
+    }
+
+    #[inline]
+    pub fn to(&self) -> u8 {
+        self.path[self.path.len() - 1]
+    }
+
+    #[inline]
+    pub fn is_capture(&self) -> bool {
+        !self.captures.is_empty()
+    }
+}
+
+impl Display for Move {
+    /// Writes the move in standard notation, e.g. `11-15` or `22x15x6`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let separator = if self.is_capture() { "x" } else { "-" };
+        let path = self
+            .path
+            .iter()
+            .map(|id| id_to_square(*id).to_string())
+            .collect::<Vec<_>>()
+            .join(separator);
+        write!(f, "{}", path)
+    }
+}
+
+/// Reasons for `Board::make_move` to reject a move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveError {
+    /// The move has no path or its path leaves the board.
+    Malformed,
+    /// There is no piece of the moving color on the starting square.
+    NoPiece(u8),
+    /// The move is not one of the legal moves of the moving color.
+    Illegal,
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            MoveError::Malformed => write!(f, "Malformed move"),
+            MoveError::NoPiece(id) => write!(f, "No piece to move on square {}", id),
+            MoveError::Illegal => write!(f, "Illegal move"),
+        }
+    }
+}
+
+impl Error for MoveError {}
+
+/// Reasons for `Board::validate` to reject a board. Each variant holds the offending square id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoardError {
+    /// A square holds a white and a black piece.
+    Overlap(u8),
+    /// A king is marked on a square without a piece.
+    KingWithoutPiece(u8),
+    /// A piece stands on a light square.
+    LightSquare(u8),
+    /// A man stands on the row it would have been crowned on.
+    ManOnCrowningRow(u8),
 }
 
-/// Information about a move.
-/// Used to undo a move.
-#[derive(Debug)]
-pub struct MoveInfo {
-    pub from: u8,
-    pub to: u8,
-    pub jumped_piece: Option<Piece>,
-    pub jumped_piece_id: Option<u8>,
-    pub crowned: bool,
+impl Display for BoardError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let (message, id) = match self {
+            BoardError::Overlap(id) => ("A white and a black piece share a square", id),
+            BoardError::KingWithoutPiece(id) => ("A king is marked on an empty square", id),
+            BoardError::LightSquare(id) => ("A piece is on a light square", id),
+            BoardError::ManOnCrowningRow(id) => ("A man is on its crowning row", id),
+        };
+        write!(
+            f,
+            "{} (row {}, column {}).",
+            message,
+            id / 8 + 1,
+            id % 8 + 1
+        )
+    }
 }
 
+impl Error for BoardError {}
+
 /// A Checkers board.
 #[derive(Clone)]
 pub struct Board {
@@ -50,64 +137,54 @@ pub struct Board {
 
     /// Bitboard of all kings. This is a subset of `white` and `black`.
     kings: Bitboard,
+
+    /// Zobrist key of the pieces. Updated incrementally whenever a piece is added or removed.
+    key: u64,
 }
 
 impl Board {
     /* --------------| Constructors |-------------- */
     pub fn new() -> Self {
-        Self {
+        let mut board = Self {
             white: DEFAULT_WHITE,
             black: DEFAULT_BLACK,
             kings: EMPTY,
-        }
+            key: 0,
+        };
+        board.key = board.compute_key();
+        board
     }
 
-    pub fn from_fen(fen: &str) -> result::Result<Self, &'static str> {
-        let mut board = Self {
+    /// Returns a board without any pieces.
+    pub fn empty() -> Self {
+        Self {
             white: EMPTY,
             black: EMPTY,
             kings: EMPTY,
-        };
-        let mut row = 7u8;
-        let mut col = 0u8;
-        for c in fen.chars() {
-            if c == '/' {
-                row -= 1;
-                col = 0;
-                if row > 7 {
-                    // return Err("Invalid FEN string");
-                    return Err("Invalid FEN string. Cannot have more than 8 rows.");
-                }
-            } else if c.is_ascii_digit() {
-                col += c.to_digit(10).unwrap() as u8;
-                if col > 8 {
-                    return Err("Invalid FEN string. Cannot have more than 8 columns per row.");
-                }
-            } else {
-                let piece = match c {
-                    'M' => Piece {
-                        color: piece::WHITE,
-                        piece_type: false,
-                    },
-                    'K' => Piece {
-                        color: piece::WHITE,
-                        piece_type: true,
-                    },
-                    'm' => Piece {
-                        color: piece::BLACK,
-                        piece_type: false,
-                    },
-                    'k' => Piece {
-                        color: piece::BLACK,
-                        piece_type: true,
-                    },
-                    _ => return Err("Invalid FEN string. Invalid character! Only allowed characters are: 'w', 'W', 'b', 'B', '/', '1', '2', '3', '4', '5', '6', '7', '8'"),
-                };
-                board.add_piece(&(row * 8 + col), &piece);
-                col += 1;
-            }
+            key: 0,
         }
-        Ok(board)
+    }
+
+    /// Returns a board with the given pieces. The bitboards are not validated.
+    pub(crate) fn from_bitboards(white: Bitboard, black: Bitboard, kings: Bitboard) -> Self {
+        let mut board = Self {
+            white,
+            black,
+            kings,
+            key: 0,
+        };
+        board.key = board.compute_key();
+        board
+    }
+
+    /// Returns the board rotated by 180 degrees with the colors swapped, so that every position
+    /// has a twin with the other side to move.
+    pub fn flipped(&self) -> Self {
+        Self::from_bitboards(
+            self.black.reverse_bits(),
+            self.white.reverse_bits(),
+            self.kings.reverse_bits(),
+        )
     }
 
     /* --------------| Static methods |-------------- */
@@ -116,11 +193,31 @@ impl Board {
     }
 
     /* --------------| Getters |-------------- */
+    /// Returns the Zobrist key of the pieces on the board. The side to move is not included.
+    #[inline]
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
     /// Returns a bitboard of all white and black pieces.
     pub fn get_white_black(&self) -> Bitboard {
         self.white | self.black
     }
 
+    /// Returns a bitboard of all pieces of `color`.
+    pub fn get_color(&self, color: PieceColor) -> Bitboard {
+        if color == piece::WHITE {
+            self.white
+        } else {
+            self.black
+        }
+    }
+
+    /// Returns a bitboard of all kings of both colors.
+    pub fn get_kings(&self) -> Bitboard {
+        self.kings
+    }
+
     /// bitboard is only allowed to have one bit set
     pub fn get_piece(&self, bitboard: Bitboard) -> Option<Piece> {
         if bitboard.count_ones() != 1 {
@@ -140,6 +237,34 @@ impl Board {
 
     /* --------------| Setters |-------------- */
 
+    /* --------------| Validation |-------------- */
+    /// Checks that the board could occur in a game: pieces only on dark squares, no square shared
+    /// by both colors, kings only on occupied squares and no man on its crowning row.
+    pub fn validate(&self) -> result::Result<(), BoardError> {
+        let first = |bitboard: Bitboard| bitboard.trailing_zeros() as u8;
+
+        let overlap = self.white & self.black;
+        return_if!(overlap != EMPTY, Err(BoardError::Overlap(first(overlap))));
+
+        let kings = self.kings & !self.get_white_black();
+        return_if!(
+            kings != EMPTY,
+            Err(BoardError::KingWithoutPiece(first(kings)))
+        );
+
+        let light = self.get_white_black() & !DARK_SQUARES;
+        return_if!(light != EMPTY, Err(BoardError::LightSquare(first(light))));
+
+        let crowning = (self.white & !self.kings & WHITE_CROWNING_ROW)
+            | (self.black & !self.kings & BLACK_CROWNING_ROW);
+        return_if!(
+            crowning != EMPTY,
+            Err(BoardError::ManOnCrowningRow(first(crowning)))
+        );
+
+        Ok(())
+    }
+
     /* --------------| Methods |-------------- */
     #[inline]
     pub fn white_count(&self) -> u8 {
@@ -161,7 +286,9 @@ impl Board {
         self.count_kings(piece::BLACK)
     }
 
-    pub fn possible_moves(&self, color: PieceColor) -> Vec<PossibleMoves> {
+    /// Returns all legal moves for `color`.
+    /// Captures are mandatory, so if any piece can jump only the capture sequences are returned.
+    pub fn possible_moves(&self, color: PieceColor) -> Vec<Move> {
         let (mut count, mut bitboard) = match color {
             piece::WHITE => (self.white.count_ones(), self.white),
             piece::BLACK => (self.black.count_ones(), self.black),
@@ -170,17 +297,27 @@ impl Board {
         return_if!(count == 0, moves);
 
         let mut jumps = Vec::new();
-        let mut force_jump = false;
 
         while count > 0 {
             let from = bitboard.trailing_zeros() as u8;
-            let (tos, move_type) = self.get_possible_moves_from(from).unwrap();
-            force_jump |= move_type == JUMP;
-            if !tos.is_empty() {
-                if move_type == JUMP {
-                    jumps.push(PossibleMoves { from, to: tos });
-                } else if !force_jump {
-                    moves.push(PossibleMoves { from, to: tos });
+            let piece = self.get_piece(1 << from).unwrap();
+            self.add_jump_sequences(
+                from,
+                &piece,
+                &mut vec![from],
+                &mut Vec::new(),
+                EMPTY,
+                &mut jumps,
+            );
+            if jumps.is_empty() {
+                for to in self.get_open_squares_from(&from, &piece) {
+                    moves.push(Move {
+                        path: vec![from, to],
+                        captures: Vec::new(),
+                        captured_kings: EMPTY,
+                        promotion: piece.piece_type == piece::MAN
+                            && Self::is_crowning_row(&to, &piece.color),
+                    });
                 }
             }
 
@@ -188,96 +325,75 @@ impl Board {
             bitboard &= !(1 << from);
         }
 
-        if force_jump {
+        if !jumps.is_empty() {
             return jumps;
         }
 
         moves
     }
 
-    // Note: this method should be used to calculate the consecutive jumps.
-    pub fn possible_jumps_from(&self, from: u8) -> Option<Vec<u8>> {
-        let piece = self.get_piece(1 << from)?;
-        Some(self.get_jumps_from(&from, &piece))
+    /// Does a move on the board after checking that it is legal for `color`.
+    pub fn make_move(&mut self, p_move: &Move, color: PieceColor) -> result::Result<(), MoveError> {
+        let p_move = self.legal_move(p_move, color)?;
+        self.move_piece(&p_move);
+        Ok(())
     }
 
-    /// Does a move on the board.
-    /// ! This method does not check if the move is valid !
-    /// Returns information about the move.
-    pub fn move_piece(&mut self, from: &u8, to: &u8) -> MoveInfo {
-        let from_bitboard = 1 << from;
-        let to_bitboard = 1 << to;
-
-        // Move piece depending on color
-        if self.white & from_bitboard != EMPTY {
-            self.white &= !from_bitboard;
-            self.white |= to_bitboard;
-        } else {
-            self.black &= !from_bitboard;
-            self.black |= to_bitboard;
-        }
-
-        // Move king if necessary
-        if self.kings & from_bitboard != EMPTY {
-            self.kings &= !from_bitboard;
-            self.kings |= to_bitboard;
+    /// Returns the legal move of `color` with the same path as `p_move`. Only the path is
+    /// compared, the captures, captured kings and promotion are taken from the move generator.
+    pub fn legal_move(&self, p_move: &Move, color: PieceColor) -> result::Result<Move, MoveError> {
+        return_if!(
+            p_move.path.len() < 2 || p_move.path.iter().any(|id| *id >= 64),
+            Err(MoveError::Malformed)
+        );
+        match self.get_piece(1 << p_move.from()) {
+            Some(piece) if piece.color == color => {}
+            _ => return Err(MoveError::NoPiece(p_move.from())),
         }
+        self.possible_moves(color)
+            .into_iter()
+            .find(|legal| legal.path == p_move.path)
+            .ok_or(MoveError::Illegal)
+    }
 
-        // Check for jumped piece
-        let mut jumped_piece = None;
-        let mut jumped_piece_id = None;
-        let move_distance = from.abs_diff(*to);
-        if move_distance == LEFT_DISTANCE * 2 || move_distance == RIGHT_DISTANCE * 2 {
-            if let Some(piece) = self.get_piece(1 << ((from + to) / 2)) {
-                jumped_piece = Some(piece);
-                jumped_piece_id = Some((from + to) / 2);
-                self.remove_piece(&jumped_piece_id.unwrap());
-            }
-        }
+    /// Does a move on the board.
+    /// ! This method does not check if the move is valid !
+    pub fn move_piece(&mut self, p_move: &Move) {
+        let from = p_move.from();
+        let to = p_move.to();
+        let mut piece = self.get_piece(1 << from).unwrap();
 
-        // Check for crowned piece
-        let mut crowned = false;
-        if (self.white & to_bitboard != EMPTY && to / 8 == 7)
-            || (self.black & to_bitboard != EMPTY && to / 8 == 0)
-        {
-            self.kings |= to_bitboard;
-            crowned = true;
+        for id in &p_move.captures {
+            self.remove_piece(id);
         }
 
-        MoveInfo {
-            from: *from,
-            to: *to,
-            jumped_piece,
-            jumped_piece_id,
-            crowned,
+        if p_move.promotion {
+            piece.piece_type = piece::KING;
         }
+        self.remove_piece(&from);
+        self.add_piece(&to, &piece);
     }
 
-    pub fn undo_move(&mut self, move_info: MoveInfo) {
-        let from_bitboard = 1 << move_info.from;
-        let to_bitboard = 1 << move_info.to;
-
-        // Move piece depending on color
-        if self.white & to_bitboard != EMPTY {
-            self.white &= !to_bitboard;
-            self.white |= from_bitboard;
-        } else {
-            self.black &= !to_bitboard;
-            self.black |= from_bitboard;
-        }
-
-        // Move king if necessary
-        if self.kings & to_bitboard != EMPTY {
-            self.kings &= !to_bitboard;
-            self.kings |= from_bitboard;
-        }
+    /// Reverts a move done by `make_move` or `move_piece`.
+    pub fn unmake_move(&mut self, p_move: &Move) {
+        let from = p_move.from();
+        let to = p_move.to();
+        let mut piece = self.get_piece(1 << to).unwrap();
 
-        if let Some(jumped_piece_id) = move_info.jumped_piece_id {
-            self.add_piece(&jumped_piece_id, &move_info.jumped_piece.unwrap());
+        if p_move.promotion {
+            piece.piece_type = piece::MAN;
         }
-
-        if move_info.crowned {
-            self.kings &= !from_bitboard;
+        self.remove_piece(&to);
+        self.add_piece(&from, &piece);
+
+        for id in &p_move.captures {
+            self.add_piece(
+                id,
+                &Piece {
+                    color: !piece.color,
+                    piece_type: p_move.captured_kings & 1 << id != EMPTY,
+                },
+            );
         }
     }
 
@@ -317,7 +433,16 @@ impl Board {
             .collect()
     }
 
-    fn get_jumps_from(&self, from: &u8, piece: &Piece) -> Vec<u8> {
+    #[inline]
+    fn is_crowning_row(id: &u8, color: &PieceColor) -> bool {
+        match *color {
+            piece::WHITE => *id / 8 == 7,
+            piece::BLACK => *id / 8 == 0,
+        }
+    }
+
+    /// Returns `(jumped square, landing square)` for every single jump of `piece` from `from`.
+    fn get_jumps_from(&self, from: &u8, piece: &Piece) -> Vec<(u8, u8)> {
         let opponent = self.get_opponent(&piece.color);
         let squares = piece.get_reachable_squares();
 
@@ -329,22 +454,55 @@ impl Board {
                     && opponent & 1 << id.0 != 0
                     && self.is_valid_and_empty(&id.1)
             })
-            .map(|id| id.1 as u8)
+            .map(|id| (id.0 as u8, id.1 as u8))
             .collect()
     }
 
-    fn get_possible_moves_from(&self, from: u8) -> Option<(Vec<u8>, MoveType)> {
-        let piece = self.get_piece(1 << from)?;
-
-        let jumps = self.get_jumps_from(&from, &piece);
-        if !jumps.is_empty() {
-            return Some((jumps, JUMP));
+    /// Collects every complete jump sequence of `piece` continuing from `from`.
+    /// A sequence ends when no further jump is possible or when a man gets crowned.
+    fn add_jump_sequences(
+        &self,
+        from: u8,
+        piece: &Piece,
+        path: &mut Vec<u8>,
+        captures: &mut Vec<u8>,
+        captured_kings: Bitboard,
+        moves: &mut Vec<Move>,
+    ) {
+        for (over, to) in self.get_jumps_from(&from, piece) {
+            let captured_kings = captured_kings | (self.kings & 1 << over);
+            let mut board = self.clone();
+            board.remove_piece(&over);
+            board.remove_piece(&from);
+            board.add_piece(&to, piece);
+
+            path.push(to);
+            captures.push(over);
+            if piece.piece_type == piece::MAN && Self::is_crowning_row(&to, &piece.color) {
+                moves.push(Move {
+                    path: path.clone(),
+                    captures: captures.clone(),
+                    captured_kings,
+                    promotion: true,
+                });
+            } else {
+                let count = moves.len();
+                board.add_jump_sequences(to, piece, path, captures, captured_kings, moves);
+                if moves.len() == count {
+                    moves.push(Move {
+                        path: path.clone(),
+                        captures: captures.clone(),
+                        captured_kings,
+                        promotion: false,
+                    });
+                }
+            }
+            path.pop();
+            captures.pop();
         }
-
-        Some((self.get_open_squares_from(&from, &piece), STANDARD))
     }
 
-    fn add_piece(&mut self, id: &u8, piece: &Piece) {
+    pub(crate) fn add_piece(&mut self, id: &u8, piece: &Piece) {
         let bitboard = 1 << id;
         if piece.color == piece::WHITE {
             self.white |= bitboard;
@@ -355,15 +513,31 @@ impl Board {
         if piece.piece_type == piece::KING {
             self.kings |= bitboard;
         }
+
+        self.key ^= zobrist::piece_key(piece, *id);
     }
 
     fn remove_piece(&mut self, id: &u8) {
         let bitboard = 1 << id;
+        if let Some(piece) = self.get_piece(bitboard) {
+            self.key ^= zobrist::piece_key(&piece, *id);
+        }
         self.white &= !bitboard;
         self.black &= !bitboard;
         self.kings &= !bitboard;
     }
 
+    fn compute_key(&self) -> u64 {
+        let mut key = 0;
+        let mut all = self.get_white_black();
+        while all != EMPTY {
+            let id = all.trailing_zeros() as u8;
+            key ^= zobrist::piece_key(&self.get_piece(1 << id).unwrap(), id);
+            all &= all - 1;
+        }
+        key
+    }
+
     /* --------------| Evaluators |-------------- */
 }
 
@@ -0,0 +1,275 @@
+use crate::{
+    board::Move,
+    pdn::PdnGame,
+    player::{MinimaxPlayer, Score, SearchResult},
+    position::Position,
+    return_if,
+    util::Rng,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::Path,
+};
+
+/// Moves up to this much worse than the best move are added by `add_searched`.
+const SEARCH_MARGIN: Score = 20;
+
+/// A move of the opening book. Moves are picked with a probability proportional to their weight.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookMove {
+    /// The move in standard notation, e.g. `11-15`.
+    pub notation: String,
+    pub weight: u32,
+}
+
+/// Reasons for an opening book file to be rejected. Lines are counted from 1.
+#[derive(Debug)]
+pub enum BookError {
+    Io(io::Error),
+    /// A line is not of the form `key move weight`.
+    InvalidLine {
+        line: usize,
+    },
+}
+
+impl Display for BookError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BookError::Io(err) => write!(f, "Could not access the opening book. {}", err),
+            BookError::InvalidLine { line } => write!(
+                f,
+                "Invalid opening book. Line {} is not of the form 'key move weight'.",
+                line
+            ),
+        }
+    }
+}
+
+impl Error for BookError {}
+
+impl From<io::Error> for BookError {
+    fn from(err: io::Error) -> Self {
+        BookError::Io(err)
+    }
+}
+
+/// Weighted moves by the key of the position they are played in.
+/// A position is also found through its flipped twin (`Board::flipped` with the other side to
+/// move), so a book of games in standard notation, where black moves first, serves games
+/// starting with white to move as well.
+#[derive(Default)]
+pub struct OpeningBook {
+    entries: HashMap<u64, Vec<BookMove>>,
+}
+
+impl OpeningBook {
+    /* --------------| Constructors |-------------- */
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a book of the first `max_plies` moves of every game, weighted by how often they
+    /// were played.
+    pub fn from_games(games: &[PdnGame], max_plies: usize) -> Self {
+        let mut book = Self::new();
+        for game in games {
+            let mut position = game.start.clone();
+            for p_move in game.moves.iter().take(max_plies) {
+                book.add(&position, p_move, 1);
+                position.move_piece(p_move);
+            }
+        }
+        book
+    }
+
+    /// Parses a book written by `Display`: one `key move weight` line per move with the
+    /// position key in hexadecimal, '#' starts a comment.
+    pub fn parse(text: &str) -> Result<Self, BookError> {
+        let mut book = Self::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = || BookError::InvalidLine { line: i + 1 };
+            let mut fields = line.split_whitespace();
+            let (Some(key), Some(notation), Some(weight), None) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                return Err(invalid());
+            };
+            let key = u64::from_str_radix(key, 16).map_err(|_| invalid())?;
+            let weight = weight.parse::<u32>().map_err(|_| invalid())?;
+            book.entries.entry(key).or_default().push(BookMove {
+                notation: notation.to_string(),
+                weight,
+            });
+        }
+        Ok(book)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BookError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /* --------------| Getters |-------------- */
+    /// Number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /* --------------| Methods |-------------- */
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), BookError> {
+        Ok(fs::write(path, self.to_string())?)
+    }
+
+    /// Adds `weight` to a move of the position.
+    pub fn add(&mut self, position: &Position, p_move: &Move, weight: u32) {
+        let notation = p_move.to_string();
+        let book_moves = self.entries.entry(position.key()).or_default();
+        match book_moves
+            .iter_mut()
+            .find(|book_move| book_move.notation == notation)
+        {
+            Some(book_move) => book_move.weight += weight,
+            None => book_moves.push(BookMove { notation, weight }),
+        }
+    }
+
+    /// Removes the moves with less than `min_weight`, e.g. moves of a single game.
+    pub fn prune(&mut self, min_weight: u32) {
+        for book_moves in self.entries.values_mut() {
+            book_moves.retain(|book_move| book_move.weight >= min_weight);
+        }
+        self.entries.retain(|_, book_moves| !book_moves.is_empty());
+    }
+
+    /// Searches `position` with `analyser` and adds every move up to `SEARCH_MARGIN` worse than
+    /// the best, weighted by how close it is. Continues with the positions after these moves
+    /// until `plies` moves deep. `analyser` reports up to `width` lines per position.
+    /// `progress` receives every searched position with its result.
+    pub fn add_searched(
+        &mut self,
+        analyser: &mut MinimaxPlayer,
+        position: &Position,
+        plies: usize,
+        width: usize,
+        mut progress: impl FnMut(&Position, &SearchResult),
+    ) {
+        analyser.set_multi_pv(width);
+        self.expand(analyser, &mut position.clone(), plies, &mut progress);
+    }
+
+    /// Returns the legal book moves of the position with their weights, adding up the weights
+    /// found through the position and its flipped twin.
+    pub fn moves(&self, position: &Position) -> Vec<(Move, u32)> {
+        let possible_moves = position.possible_moves();
+        let flipped = Position::from_board(position.board().flipped(), !position.turn());
+
+        let mut moves: Vec<(Move, u32)> = Vec::new();
+        for (key, flip) in [(position.key(), false), (flipped.key(), true)] {
+            for book_move in self.entries.get(&key).into_iter().flatten() {
+                let Some(p_move) = possible_moves
+                    .iter()
+                    .find(|p_move| Self::notation(p_move, flip) == book_move.notation)
+                else {
+                    continue;
+                };
+                match moves.iter_mut().find(|(known, _)| known == p_move) {
+                    Some((_, weight)) => *weight += book_move.weight,
+                    None => moves.push((p_move.clone(), book_move.weight)),
+                }
+            }
+        }
+        moves
+    }
+
+    /// Picks a book move of the position at random, with probabilities proportional to the
+    /// weights.
+    pub(crate) fn pick(&self, position: &Position, rng: &mut Rng) -> Option<Move> {
+        let moves = self.moves(position);
+        let total = moves.iter().map(|(_, weight)| *weight as u64).sum::<u64>();
+        return_if!(total == 0, None);
+
+        let mut choice = rng.below(total);
+        for (p_move, weight) in moves {
+            return_if!(choice < weight as u64, Some(p_move));
+            choice -= weight as u64;
+        }
+        None
+    }
+
+    /* --------------| Private methods |-------------- */
+    fn expand(
+        &mut self,
+        analyser: &mut MinimaxPlayer,
+        position: &mut Position,
+        plies: usize,
+        progress: &mut dyn FnMut(&Position, &SearchResult),
+    ) {
+        // Transpositions are only searched once
+        if plies == 0 || self.entries.contains_key(&position.key()) {
+            return;
+        }
+
+        let result = analyser.analyse(position);
+        progress(position, &result);
+        let book_moves = result
+            .lines
+            .iter()
+            .filter(|line| result.score - line.score <= SEARCH_MARGIN)
+            .map(|line| {
+                let weight = (SEARCH_MARGIN - (result.score - line.score)) as u32 + 1;
+                (line.pv[0].clone(), weight)
+            })
+            .collect::<Vec<_>>();
+
+        for (p_move, weight) in &book_moves {
+            self.add(position, p_move, *weight);
+        }
+        for (p_move, _) in &book_moves {
+            position.move_piece(p_move);
+            self.expand(analyser, position, plies - 1, progress);
+            position.unmake_move(p_move);
+        }
+    }
+
+    /// The notation of a move, or of the same move on the flipped board.
+    fn notation(p_move: &Move, flip: bool) -> String {
+        if !flip {
+            return p_move.to_string();
+        }
+        Move {
+            path: p_move.path.iter().map(|id| 63 - id).collect(),
+            captures: p_move.captures.iter().map(|id| 63 - id).collect(),
+            captured_kings: p_move.captured_kings.reverse_bits(),
+            promotion: p_move.promotion,
+        }
+        .to_string()
+    }
+}
+
+impl Display for OpeningBook {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut keys = self.entries.keys().collect::<Vec<_>>();
+        keys.sort();
+        for key in keys {
+            for book_move in &self.entries[key] {
+                writeln!(
+                    f,
+                    "{:016x} {} {}",
+                    key, book_move.notation, book_move.weight
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+use super::{
+    table::{decode, encode, DRAW, INVALID},
+    EgdbError, EndgameDatabase, Material, Outcome, Table, MAX_DISTANCE,
+};
+use crate::{board::Board, break_if, piece, return_if};
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+/// Marks a successor in another table. The low byte holds its entry.
+const EXTERNAL: u32 = 1 << 31;
+
+impl EndgameDatabase {
+    /// Builds the tables of every material with up to `max_pieces` pieces by retrograde analysis
+    /// and writes them to `dir`. `progress` receives every table once it is written.
+    ///
+    /// Captures and promotions lead to materials that are solved before, so each table only
+    /// depends on itself and its flipped twin, which are solved together: positions without moves
+    /// are lost, and in rising number of plies a position is won if a move leads to a position lost
+    /// one ply earlier and lost if every move leads to a position won by the opponent.
+    /// Positions never decided this way are draws.
+    pub fn generate(
+        dir: impl AsRef<Path>,
+        max_pieces: u8,
+        mut progress: impl FnMut(&Table),
+    ) -> Result<Self, EgdbError> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        let mut files = HashMap::new();
+        let mut tables = HashMap::new();
+        for material in Material::all(max_pieces) {
+            if tables.contains_key(&material) {
+                continue;
+            }
+            let group = if material.flipped() == material {
+                vec![material]
+            } else {
+                vec![material, material.flipped()]
+            };
+            for table in solve(&group, &tables)? {
+                files.insert(table.material(), table.save(dir)?);
+                progress(&table);
+                tables.insert(table.material(), table);
+            }
+        }
+
+        let cache = tables
+            .into_iter()
+            .map(|(material, table)| (material, Some(Arc::new(table))))
+            .collect();
+        Ok(Self::new(files, cache))
+    }
+}
+
+/// Solves the materials of `group` together, using the finished `tables` for captures and
+/// promotions.
+fn solve(group: &[Material], tables: &HashMap<Material, Table>) -> Result<Vec<Table>, EgdbError> {
+    let bases = group
+        .iter()
+        .scan(0, |base, material| {
+            let start = *base;
+            *base += material.size();
+            Some(start)
+        })
+        .collect::<Vec<_>>();
+    let total = group.iter().map(|material| material.size()).sum::<usize>();
+    // Successors in the group are stored as indices below the `EXTERNAL` flag
+    return_if!(
+        total > EXTERNAL as usize,
+        Err(EgdbError::TooLarge(group[0]))
+    );
+
+    // The successors of every position in compressed rows: indices into `entries` for
+    // positions of the group, entries of the finished tables otherwise
+    let mut entries = vec![DRAW; total];
+    let mut offsets = Vec::with_capacity(total + 1);
+    let mut successors = Vec::new();
+    let mut longest_external = 0;
+    offsets.push(0);
+
+    for (base, material) in bases.iter().zip(group) {
+        for index in 0..material.size() {
+            let entry = &mut entries[base + index];
+            match material.board(index) {
+                None => *entry = INVALID,
+                Some(board) => {
+                    let possible_moves = board.possible_moves(piece::WHITE);
+                    if possible_moves.is_empty() {
+                        *entry = encode(Outcome::Loss(0));
+                    }
+                    for p_move in possible_moves {
+                        let mut next = board.clone();
+                        next.move_piece(&p_move);
+                        let successor = successor(&next.flipped(), group, &bases, tables);
+                        if successor & EXTERNAL != 0 {
+                            longest_external = longest_external.max(distance(successor as u8));
+                        }
+                        successors.push(successor);
+                    }
+                }
+            }
+            offsets.push(successors.len());
+        }
+    }
+
+    let mut plies = 1;
+    loop {
+        let mut changed = false;
+        for position in 0..total {
+            if entries[position] != DRAW {
+                continue;
+            }
+
+            let mut won = false;
+            // Whether every move leads to a position won by the opponent in fewer plies
+            let mut lost = true;
+            for successor in &successors[offsets[position]..offsets[position + 1]] {
+                let entry = if successor & EXTERNAL != 0 {
+                    *successor as u8
+                } else {
+                    entries[*successor as usize]
+                };
+                match decode(entry) {
+                    Some(Outcome::Loss(distance)) if distance + 1 == plies => {
+                        won = true;
+                        break;
+                    }
+                    Some(Outcome::Win(distance)) if distance < plies => {}
+                    _ => lost = false,
+                }
+            }
+
+            if won {
+                entries[position] = encode(Outcome::Win(plies));
+            } else if lost {
+                entries[position] = encode(Outcome::Loss(plies));
+            }
+            changed |= won || lost;
+        }
+
+        // Nothing can be decided anymore once a ply neither decided a position of the group
+        // nor can use a longer result of another table
+        break_if!(!changed && plies > longest_external);
+        if plies == MAX_DISTANCE {
+            return Err(EgdbError::DistanceOverflow(group[0]));
+        }
+        plies += 1;
+    }
+
+    Ok(bases
+        .iter()
+        .zip(group)
+        .map(|(base, material)| {
+            Table::new(*material, entries[*base..base + material.size()].to_vec())
+        })
+        .collect())
+}
+
+/// Returns the successor entry of a board with white to move.
+fn successor(
+    board: &Board,
+    group: &[Material],
+    bases: &[usize],
+    tables: &HashMap<Material, Table>,
+) -> u32 {
+    if board.white_count() == 0 {
+        return EXTERNAL | encode(Outcome::Loss(0)) as u32;
+    }
+    let material = Material::of(board);
+    let index = material.index(board);
+    match group.iter().position(|other| *other == material) {
+        Some(i) => (bases[i] + index) as u32,
+        None => EXTERNAL | tables[&material].entry(index) as u32,
+    }
+}
+
+fn distance(entry: u8) -> u8 {
+    match decode(entry) {
+        Some(Outcome::Win(plies) | Outcome::Loss(plies)) => plies,
+        _ => 0,
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{
+    board::{Bitboard, Board},
+    fen::square_to_id,
+    piece, return_if,
+};
+use std::fmt::{self, Display, Formatter};
+
+/// Number of dark squares.
+const SQUARES: usize = 32;
+/// Number of squares a man can stand on, all but its crowning row.
+const MAN_SQUARES: usize = 28;
+/// Largest number of pieces of one kind that can be indexed.
+pub(crate) const MAX_GROUP: usize = 12;
+
+/// Board ids of the dark squares, indexed by standard square number - 1.
+const SQUARE_IDS: [u8; SQUARES] = square_ids();
+/// Binomial coefficients `BINOMIAL[n][k]` for the ranks of piece placements.
+const BINOMIAL: [[usize; MAX_GROUP + 1]; SQUARES + 1] = binomials();
+
+/// The number of men and kings of each color, identifying one endgame table.
+/// Tables only hold positions with white to move, the positions with black to move are looked up
+/// with the board flipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Material {
+    pub white_men: u8,
+    pub white_kings: u8,
+    pub black_men: u8,
+    pub black_kings: u8,
+}
+
+impl Material {
+    /* --------------| Constructors |-------------- */
+    pub fn new(white_men: u8, white_kings: u8, black_men: u8, black_kings: u8) -> Self {
+        Self {
+            white_men,
+            white_kings,
+            black_men,
+            black_kings,
+        }
+    }
+
+    pub fn of(board: &Board) -> Self {
+        Self::new(
+            board.white_count() - board.white_king_count(),
+            board.white_king_count(),
+            board.black_count() - board.black_king_count(),
+            board.black_king_count(),
+        )
+    }
+
+    /// Returns every material with both colors on the board and at most `max_pieces` pieces,
+    /// in the order the tables have to be generated: fewer pieces first, then fewer men.
+    /// Captures and promotions always lead to a material earlier in this order.
+    pub fn all(max_pieces: u8) -> Vec<Self> {
+        let mut materials = Vec::new();
+        for white in 1..max_pieces {
+            for black in 1..=max_pieces - white {
+                for white_men in 0..=white {
+                    for black_men in 0..=black {
+                        materials.push(Self::new(
+                            white_men,
+                            white - white_men,
+                            black_men,
+                            black - black_men,
+                        ));
+                    }
+                }
+            }
+        }
+        materials.sort_by_key(|material| (material.pieces(), material.men(), *material));
+        materials
+    }
+
+    /* --------------| Methods |-------------- */
+    #[inline]
+    pub fn pieces(self) -> u8 {
+        self.white_men + self.white_kings + self.black_men + self.black_kings
+    }
+
+    #[inline]
+    pub fn men(self) -> u8 {
+        self.white_men + self.black_men
+    }
+
+    /// The material after `Board::flipped`.
+    pub fn flipped(self) -> Self {
+        Self::new(
+            self.black_men,
+            self.black_kings,
+            self.white_men,
+            self.white_kings,
+        )
+    }
+
+    /// Number of entries of the table, including placements with pieces sharing a square.
+    pub fn size(self) -> usize {
+        self.groups()
+            .iter()
+            .map(|(count, squares)| BINOMIAL[*squares][*count as usize])
+            .product()
+    }
+
+    /// Returns the index of a board of this material in its table.
+    pub fn index(self, board: &Board) -> usize {
+        let white = board.get_color(piece::WHITE);
+        let black = board.get_color(piece::BLACK);
+        let kings = board.get_kings();
+        // White men never stand on squares 1-4 and black men never on squares 29-32
+        let placements = [
+            Self::squares(white & !kings) >> 4,
+            Self::squares(white & kings),
+            Self::squares(black & !kings),
+            Self::squares(black & kings),
+        ];
+
+        self.groups()
+            .iter()
+            .zip(placements)
+            .fold(0, |index, ((_, squares), placement)| {
+                index * BINOMIAL[*squares][placement.count_ones() as usize] + Self::rank(placement)
+            })
+    }
+
+    /// Returns the board of an index, or `None` if two pieces share a square.
+    pub fn board(self, index: usize) -> Option<Board> {
+        let [white_men, white_kings, black_men, black_kings] = self.placements(index)?;
+        let white = Self::bitboard(white_men | white_kings);
+        let black = Self::bitboard(black_men | black_kings);
+        let kings = Self::bitboard(white_kings | black_kings);
+        Some(Board::from_bitboards(white, black, kings))
+    }
+
+    /// Whether no two pieces of the index share a square.
+    pub fn is_valid(self, index: usize) -> bool {
+        self.placements(index).is_some()
+    }
+
+    /* --------------| Private methods |-------------- */
+    /// The number of pieces and squares of each kind: white men, white kings, black men and
+    /// black kings.
+    fn groups(self) -> [(u8, usize); 4] {
+        [
+            (self.white_men, MAN_SQUARES),
+            (self.white_kings, SQUARES),
+            (self.black_men, MAN_SQUARES),
+            (self.black_kings, SQUARES),
+        ]
+    }
+
+    /// Returns the squares of the white men, white kings, black men and black kings of an index,
+    /// or `None` if two pieces share a square.
+    fn placements(self, mut index: usize) -> Option<[u32; 4]> {
+        let mut placements = [0; 4];
+        for (i, (count, squares)) in self.groups().iter().enumerate().rev() {
+            let size = BINOMIAL[*squares][*count as usize];
+            placements[i] = Self::unrank(index % size, *count, *squares);
+            index /= size;
+        }
+        placements[0] <<= 4;
+
+        let [white_men, white_kings, black_men, black_kings] = placements;
+        return_if!(
+            white_men & white_kings != 0
+                || (white_men | white_kings) & (black_men | black_kings) != 0
+                || black_men & black_kings != 0,
+            None
+        );
+        Some(placements)
+    }
+
+    /// Converts a bitboard to a mask of standard squares, bit `n` for square `n + 1`.
+    fn squares(bitboard: Bitboard) -> u32 {
+        SQUARE_IDS
+            .iter()
+            .enumerate()
+            .filter(|(_, id)| bitboard & 1 << **id != 0)
+            .fold(0, |squares, (square, _)| squares | 1 << square)
+    }
+
+    fn bitboard(squares: u32) -> Bitboard {
+        SQUARE_IDS
+            .iter()
+            .enumerate()
+            .filter(|(square, _)| squares & 1 << square != 0)
+            .fold(0, |bitboard, (_, id)| bitboard | 1 << id)
+    }
+
+    /// The position of a set of squares in the colexicographic order of all sets of its size.
+    fn rank(mut squares: u32) -> usize {
+        let mut rank = 0;
+        let mut i = 1;
+        while squares != 0 {
+            rank += BINOMIAL[squares.trailing_zeros() as usize][i];
+            squares &= squares - 1;
+            i += 1;
+        }
+        rank
+    }
+
+    /// The set of `count` squares out of `squares` with the given rank.
+    fn unrank(mut rank: usize, count: u8, squares: usize) -> u32 {
+        let mut placement = 0;
+        let mut square = squares;
+        for i in (1..=count as usize).rev() {
+            square -= 1;
+            while BINOMIAL[square][i] > rank {
+                square -= 1;
+            }
+            rank -= BINOMIAL[square][i];
+            placement |= 1 << square;
+        }
+        placement
+    }
+}
+
+impl Display for Material {
+    /// Writes the material like `2M 1K vs 0M 2K`, white first.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}M {}K vs {}M {}K",
+            self.white_men, self.white_kings, self.black_men, self.black_kings
+        )
+    }
+}
+
+const fn square_ids() -> [u8; SQUARES] {
+    let mut ids = [0; SQUARES];
+    let mut square = 0;
+    while square < SQUARES {
+        ids[square] = match square_to_id(square as u8 + 1) {
+            Some(id) => id,
+            None => panic!("Invalid square"),
+        };
+        square += 1;
+    }
+    ids
+}
+
+const fn binomials() -> [[usize; MAX_GROUP + 1]; SQUARES + 1] {
+    let mut binomial = [[0; MAX_GROUP + 1]; SQUARES + 1];
+    let mut n = 0;
+    while n <= SQUARES {
+        binomial[n][0] = 1;
+        let mut k = 1;
+        while k <= MAX_GROUP && k <= n {
+            binomial[n][k] = binomial[n - 1][k - 1] + binomial[n - 1][k];
+            k += 1;
+        }
+        n += 1;
+    }
+    binomial
+}
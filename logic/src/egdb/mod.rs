@@ -0,0 +1,199 @@
+mod generate;
+mod index;
+mod table;
+mod validate;
+
+pub use index::Material;
+pub use table::{Table, TableStats};
+pub use validate::{Mismatch, ValidationReport};
+
+use crate::{
+    board::{Board, Move},
+    piece,
+    position::Position,
+    return_if,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+/// The longest distance to the end of the game a table can store, in plies.
+pub const MAX_DISTANCE: u8 = 253;
+
+/// The value of a position for the side to move with perfect play.
+/// Wins and losses hold the number of plies until the side to move wins or loses,
+/// i.e. until the losing side has no moves left.
+/// Repetitions and the no-progress rule are not considered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Win(u8),
+    Loss(u8),
+    Draw,
+}
+
+/// Reasons for the generation or loading of endgame tables to fail.
+#[derive(Debug)]
+pub enum EgdbError {
+    Io(io::Error),
+    /// A file is not an endgame table or is damaged.
+    InvalidFile(PathBuf),
+    /// A win takes longer than `MAX_DISTANCE` plies.
+    DistanceOverflow(Material),
+    /// The positions of a material and its flipped twin cannot be indexed together.
+    TooLarge(Material),
+}
+
+impl Display for EgdbError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EgdbError::Io(err) => write!(f, "Could not access the endgame database. {}", err),
+            EgdbError::InvalidFile(path) => {
+                write!(f, "Invalid endgame table '{}'.", path.display())
+            }
+            EgdbError::DistanceOverflow(material) => write!(
+                f,
+                "A win in the {} endgame takes longer than {} plies.",
+                material, MAX_DISTANCE
+            ),
+            EgdbError::TooLarge(material) => write!(
+                f,
+                "The {} endgame has too many positions to be solved.",
+                material
+            ),
+        }
+    }
+}
+
+impl Error for EgdbError {}
+
+impl From<io::Error> for EgdbError {
+    fn from(err: io::Error) -> Self {
+        EgdbError::Io(err)
+    }
+}
+
+/// The endgame tables of a directory. Tables are loaded on their first probe and kept in memory.
+/// The database can be shared by the threads of a search.
+pub struct EndgameDatabase {
+    max_pieces: u8,
+    files: HashMap<Material, PathBuf>,
+    /// The tables loaded so far, `None` if loading failed.
+    cache: RwLock<HashMap<Material, Option<Arc<Table>>>>,
+}
+
+impl EndgameDatabase {
+    /* --------------| Constructors |-------------- */
+    /// Finds the tables of a directory written by `generate`. Only their headers are read.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, EgdbError> {
+        let mut files = HashMap::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == table::EXTENSION)
+            {
+                files.insert(table::read_material(&path)?, path);
+            }
+        }
+        Ok(Self::new(files, HashMap::new()))
+    }
+
+    fn new(
+        files: HashMap<Material, PathBuf>,
+        cache: HashMap<Material, Option<Arc<Table>>>,
+    ) -> Self {
+        // The largest piece count for which the tables of every material are present
+        let mut max_pieces = 1;
+        while Material::all(max_pieces + 1)
+            .iter()
+            .all(|material| files.contains_key(material))
+        {
+            max_pieces += 1;
+        }
+
+        Self {
+            max_pieces,
+            files,
+            cache: RwLock::new(cache),
+        }
+    }
+
+    /* --------------| Getters |-------------- */
+    /// Every position with at most this many pieces can be probed.
+    pub fn max_pieces(&self) -> u8 {
+        self.max_pieces
+    }
+
+    /// Number of tables loaded into memory.
+    pub fn loaded_tables(&self) -> usize {
+        let cache = self.cache.read().unwrap();
+        cache.values().filter(|table| table.is_some()).count()
+    }
+
+    /* --------------| Methods |-------------- */
+    /// Returns the table of a material, loading it if needed.
+    pub fn table(&self, material: Material) -> Result<Option<Arc<Table>>, EgdbError> {
+        if let Some(table) = self.cache.read().unwrap().get(&material) {
+            return Ok(table.clone());
+        }
+        let Some(path) = self.files.get(&material) else {
+            return Ok(None);
+        };
+
+        let table = Table::load(path).map(Arc::new);
+        // A damaged table is not read again
+        let mut cache = self.cache.write().unwrap();
+        cache.insert(material, table.as_ref().ok().cloned());
+        table.map(Some)
+    }
+
+    /// Returns the value of the position for the side to move, or `None` if it has too many
+    /// pieces for the database or its table can not be loaded.
+    pub fn probe(&self, position: &Position) -> Option<Outcome> {
+        self.probe_board(position.board(), position.turn())
+    }
+
+    /// Returns the move with the best outcome for the side to move: the fastest win, else a draw,
+    /// else the slowest loss. `None` if the position is not in the database or has no moves.
+    pub fn best_move(&self, position: &Position) -> Option<Move> {
+        let mut position = position.clone();
+        let mut best: Option<(i32, Move)> = None;
+        for p_move in position.possible_moves() {
+            position.move_piece(&p_move);
+            let outcome = self.probe(&position);
+            position.unmake_move(&p_move);
+            // The outcome for the opponent, ranked from the side to move's point of view
+            let rank = match outcome? {
+                Outcome::Loss(plies) => MAX_DISTANCE as i32 + 1 - plies as i32,
+                Outcome::Draw => 0,
+                Outcome::Win(plies) => plies as i32 - MAX_DISTANCE as i32 - 1,
+            };
+            if best.as_ref().is_none_or(|(best_rank, _)| rank > *best_rank) {
+                best = Some((rank, p_move));
+            }
+        }
+        best.map(|(_, p_move)| p_move)
+    }
+
+    /// Returns the value of the board with `turn` to move, see `probe`.
+    pub fn probe_board(&self, board: &Board, turn: piece::PieceColor) -> Option<Outcome> {
+        return_if!(
+            board.white_count() + board.black_count() > self.max_pieces,
+            None
+        );
+        // The tables only hold positions with white to move
+        let board = if turn == piece::WHITE {
+            board.clone()
+        } else {
+            board.flipped()
+        };
+        return_if!(board.white_count() == 0, Some(Outcome::Loss(0)));
+        let material = Material::of(&board);
+        self.table(material).ok()??.get(material.index(&board))
+    }
+}
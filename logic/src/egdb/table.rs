@@ -0,0 +1,246 @@
+use super::{index::MAX_GROUP, EgdbError, Material, Outcome, MAX_DISTANCE};
+use crate::return_if;
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// File extension of the tables.
+pub(crate) const EXTENSION: &str = "egdb";
+const MAGIC: &[u8; 4] = b"CKEG";
+const VERSION: u8 = 1;
+/// Magic, version, material and entry count.
+const HEADER_SIZE: usize = 13;
+/// Longest run and literal sequence of one PackBits header.
+const MAX_RUN: usize = 129;
+const MAX_LITERALS: usize = 128;
+
+/* Entry encoding, decided positions store the distance + 1 */
+pub(crate) const DRAW: u8 = 0;
+/// Two pieces share a square.
+pub(crate) const INVALID: u8 = u8::MAX;
+
+/// The outcome of every position of one material with white to move, one byte per position.
+/// On disk the invalid placements are left out and the remaining entries are run-length encoded.
+pub struct Table {
+    material: Material,
+    entries: Vec<u8>,
+}
+
+/// Number of positions of a table by outcome.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TableStats {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+    /// The longest win or loss in plies.
+    pub longest: u8,
+}
+
+impl Table {
+    /* --------------| Constructors |-------------- */
+    pub(crate) fn new(material: Material, entries: Vec<u8>) -> Self {
+        Self { material, entries }
+    }
+
+    /// Reads a table written by `save`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, EgdbError> {
+        let path = path.as_ref();
+        let invalid = || EgdbError::InvalidFile(path.to_path_buf());
+        let bytes = fs::read(path)?;
+        let material = parse_header(&bytes).ok_or_else(invalid)?;
+        let size = material.size();
+
+        // Invalid placements are not stored
+        let mut stored = unpack(&bytes[HEADER_SIZE..])
+            .ok_or_else(invalid)?
+            .into_iter();
+        let mut entries = Vec::with_capacity(size);
+        for index in 0..size {
+            entries.push(if material.is_valid(index) {
+                stored.next().ok_or_else(invalid)?
+            } else {
+                INVALID
+            });
+        }
+        return_if!(stored.next().is_some(), Err(invalid()));
+
+        Ok(Self::new(material, entries))
+    }
+
+    /* --------------| Getters |-------------- */
+    pub fn material(&self) -> Material {
+        self.material
+    }
+
+    /// Number of entries, including invalid placements.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the outcome of the position with `index`, or `None` for an invalid placement.
+    pub fn get(&self, index: usize) -> Option<Outcome> {
+        decode(*self.entries.get(index)?)
+    }
+
+    pub(crate) fn entry(&self, index: usize) -> u8 {
+        self.entries[index]
+    }
+
+    /* --------------| Methods |-------------- */
+    pub fn stats(&self) -> TableStats {
+        let mut stats = TableStats::default();
+        for entry in &self.entries {
+            match decode(*entry) {
+                Some(Outcome::Win(plies)) => {
+                    stats.wins += 1;
+                    stats.longest = stats.longest.max(plies);
+                }
+                Some(Outcome::Loss(plies)) => {
+                    stats.losses += 1;
+                    stats.longest = stats.longest.max(plies);
+                }
+                Some(Outcome::Draw) => stats.draws += 1,
+                None => {}
+            }
+        }
+        stats
+    }
+
+    /// Writes the table to `dir` as `<material>.egdb`, e.g. `0201.egdb` for two white kings
+    /// against a black king.
+    /// Returns the path of the written file.
+    pub fn save(&self, dir: impl AsRef<Path>) -> Result<PathBuf, EgdbError> {
+        let material = self.material;
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.extend([
+            material.white_men,
+            material.white_kings,
+            material.black_men,
+            material.black_kings,
+        ]);
+        bytes.extend((self.entries.len() as u32).to_le_bytes());
+
+        let stored = self
+            .entries
+            .iter()
+            .copied()
+            .filter(|entry| *entry != INVALID)
+            .collect::<Vec<_>>();
+        pack(&stored, &mut bytes);
+
+        let path = dir.as_ref().join(file_name(material));
+        fs::write(&path, bytes)?;
+        Ok(path)
+    }
+}
+
+/// Reads the material of a table without loading its entries.
+pub(crate) fn read_material(path: &Path) -> Result<Material, EgdbError> {
+    let mut header = [0; HEADER_SIZE];
+    File::open(path)?
+        .read_exact(&mut header)
+        .map_err(|_| EgdbError::InvalidFile(path.to_path_buf()))?;
+    parse_header(&header).ok_or_else(|| EgdbError::InvalidFile(path.to_path_buf()))
+}
+
+/// Returns the material of a table file, or `None` if it does not start with a valid header.
+fn parse_header(bytes: &[u8]) -> Option<Material> {
+    return_if!(
+        bytes.len() < HEADER_SIZE
+            || &bytes[..4] != MAGIC
+            || bytes[4] != VERSION
+            || bytes[5..9].iter().any(|count| *count as usize > MAX_GROUP),
+        None
+    );
+    let material = Material::new(bytes[5], bytes[6], bytes[7], bytes[8]);
+    let size = u32::from_le_bytes([bytes[9], bytes[10], bytes[11], bytes[12]]) as usize;
+    Some(material).filter(|material| material.size() == size)
+}
+
+fn file_name(material: Material) -> String {
+    format!(
+        "{}{}{}{}.{}",
+        material.white_men,
+        material.white_kings,
+        material.black_men,
+        material.black_kings,
+        EXTENSION
+    )
+}
+
+pub(crate) fn encode(outcome: Outcome) -> u8 {
+    match outcome {
+        Outcome::Win(plies) | Outcome::Loss(plies) => plies.min(MAX_DISTANCE) + 1,
+        Outcome::Draw => DRAW,
+    }
+}
+
+pub(crate) fn decode(entry: u8) -> Option<Outcome> {
+    match entry {
+        DRAW => Some(Outcome::Draw),
+        INVALID => None,
+        // The side that wins makes the last move, so wins take an odd number of plies
+        _ if entry.is_multiple_of(2) => Some(Outcome::Win(entry - 1)),
+        _ => Some(Outcome::Loss(entry - 1)),
+    }
+}
+
+/// Run-length encodes `entries` with PackBits: a header byte below 128 is followed by that many
+/// + 1 literal entries, a header byte `n` from 128 stands for `n - 126` copies of the next entry.
+fn pack(entries: &[u8], bytes: &mut Vec<u8>) {
+    // Number of entries before `i` waiting to be written as literals
+    let mut literals = 0;
+    let mut i = 0;
+    while i < entries.len() {
+        let run = entries[i..]
+            .iter()
+            .take(MAX_RUN)
+            .take_while(|entry| **entry == entries[i])
+            .count();
+        if run >= 2 {
+            flush_literals(&entries[i - literals..i], bytes);
+            literals = 0;
+            bytes.push((run + 126) as u8);
+            bytes.push(entries[i]);
+            i += run;
+        } else {
+            literals += 1;
+            i += 1;
+            if literals == MAX_LITERALS {
+                flush_literals(&entries[i - literals..i], bytes);
+                literals = 0;
+            }
+        }
+    }
+    flush_literals(&entries[i - literals..], bytes);
+}
+
+fn flush_literals(literals: &[u8], bytes: &mut Vec<u8>) {
+    if !literals.is_empty() {
+        bytes.push((literals.len() - 1) as u8);
+        bytes.extend(literals);
+    }
+}
+
+/// Reverts `pack`, or returns `None` if the data ends in the middle of a run.
+fn unpack(mut bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut entries = Vec::new();
+    while let Some((&header, rest)) = bytes.split_first() {
+        if header < 128 {
+            let count = header as usize + 1;
+            entries.extend(rest.get(..count)?);
+            bytes = &rest[count..];
+        } else {
+            entries.extend(std::iter::repeat_n(*rest.first()?, header as usize - 126));
+            bytes = &rest[1..];
+        }
+    }
+    Some(entries)
+}
@@ -0,0 +1,119 @@
+use super::{EndgameDatabase, Material, Outcome};
+use crate::{piece, position::Position, return_if};
+use std::collections::HashMap;
+
+/// A position where the database and the search disagree.
+pub struct Mismatch {
+    pub position: Position,
+    /// The outcome stored in the database.
+    pub expected: Option<Outcome>,
+    /// The outcome found by the search, `None` if it is not decided within the search depth.
+    pub found: Option<Outcome>,
+}
+
+#[derive(Default)]
+pub struct ValidationReport {
+    /// Number of positions compared, counting both sides to move.
+    pub positions: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl EndgameDatabase {
+    /// Compares the database with a brute-force search on every position with at most
+    /// `max_pieces` pieces, with either side to move. The search knows nothing about the database:
+    /// it looks at every line up to `depth` plies, so wins and losses within `depth` plies have to
+    /// match exactly and longer ones as well as draws must not be decided by it.
+    /// `progress` receives every material once it is compared.
+    pub fn validate(
+        &self,
+        max_pieces: u8,
+        depth: u8,
+        mut progress: impl FnMut(Material),
+    ) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let mut solver = Solver::default();
+
+        for material in Material::all(max_pieces.min(self.max_pieces())) {
+            for index in 0..material.size() {
+                let Some(board) = material.board(index) else {
+                    continue;
+                };
+                for position in [
+                    Position::from_board(board.clone(), piece::WHITE),
+                    Position::from_board(board.flipped(), piece::BLACK),
+                ] {
+                    let expected = self.probe(&position);
+                    let found = solver.solve(&mut position.clone(), depth);
+                    let within_depth = match expected {
+                        Some(Outcome::Win(plies) | Outcome::Loss(plies)) => plies <= depth,
+                        _ => false,
+                    };
+                    if found != expected.filter(|_| within_depth) {
+                        report.mismatches.push(Mismatch {
+                            position,
+                            expected,
+                            found,
+                        });
+                    }
+                    report.positions += 1;
+                }
+            }
+            progress(material);
+        }
+
+        report
+    }
+}
+
+/// Depth limited search for forced wins, remembering the results of every position.
+#[derive(Default)]
+struct Solver {
+    /// The outcome of a position, or the depth up to which it is not decided.
+    results: HashMap<u64, Result<Outcome, u8>>,
+}
+
+impl Solver {
+    /// Returns the outcome of the position if it is decided within `depth` plies.
+    fn solve(&mut self, position: &mut Position, depth: u8) -> Option<Outcome> {
+        let key = position.key();
+        match self.results.get(&key) {
+            Some(Ok(outcome)) => {
+                return Self::plies(*outcome)
+                    .filter(|plies| *plies <= depth)
+                    .map(|_| *outcome);
+            }
+            Some(Err(searched)) if *searched >= depth => return None,
+            _ => {}
+        }
+
+        let possible_moves = position.possible_moves();
+        return_if!(possible_moves.is_empty(), Some(Outcome::Loss(0)));
+        return_if!(depth == 0, None);
+
+        let mut win = None;
+        let mut loss = Some(0);
+        for p_move in &possible_moves {
+            position.move_piece(p_move);
+            let outcome = self.solve(position, depth - 1);
+            position.unmake_move(p_move);
+            match outcome {
+                Some(Outcome::Loss(plies)) => {
+                    win = Some(win.map_or(plies + 1, |win: u8| win.min(plies + 1)));
+                }
+                Some(Outcome::Win(plies)) => loss = loss.map(|loss: u8| loss.max(plies + 1)),
+                _ => loss = None,
+            }
+        }
+
+        let outcome = win.map(Outcome::Win).or(loss.map(Outcome::Loss));
+        self.results.insert(key, outcome.ok_or(depth));
+        outcome
+    }
+
+    fn plies(outcome: Outcome) -> Option<u8> {
+        match outcome {
+            Outcome::Win(plies) | Outcome::Loss(plies) => Some(plies),
+            Outcome::Draw => None,
+        }
+    }
+}
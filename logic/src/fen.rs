@@ -0,0 +1,377 @@
+use crate::{
+    board::{Board, BoardError},
+    piece::{self, Piece, PieceColor},
+    position::Position,
+    return_if,
+};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+/// The supported board notations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FenFormat {
+    /// One group per row from top to bottom, e.g. `1m1m1m1m/m1m1m1m1/.../M1M1M1M1 W`.
+    /// 'm'/'M' are black/white men, 'k'/'K' black/white kings and digits count empty squares.
+    /// The side to move is an optional trailing 'W' or 'B' and defaults to white.
+    Rows,
+    /// The standard PDN FEN, e.g. `W:W21,22,K30:B1,2,3`.
+    /// The first letter is the side to move, followed by the squares (1-32) of each color.
+    /// Kings are prefixed with 'K' and ranges like `1-12` are allowed.
+    Pdn,
+}
+
+/// Converts a standard square number (1-32) to a board id.
+/// Square 1 is on black's side of the board, square 32 on white's side.
+pub const fn square_to_id(square: u8) -> Option<u8> {
+    return_if!(square < 1 || square > 32, None);
+    let row = 7 - (square - 1) / 4;
+    let col = (square - 1) % 4 * 2 + row % 2;
+    Some(row * 8 + col)
+}
+
+/// Converts a board id to its standard square number (1-32).
+pub fn id_to_square(id: u8) -> u8 {
+    (7 - id / 8) * 4 + (id % 8) / 2 + 1
+}
+
+/// Reasons for a FEN string to be rejected.
+/// Indices are byte offsets into the parsed string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    /// A character that is not allowed in the row format.
+    InvalidCharacter { index: usize, character: char },
+    /// The row format does not have exactly 8 rows.
+    InvalidRowCount { rows: usize },
+    /// A row does not cover exactly 8 columns. Rows are counted from the top, starting at 1.
+    InvalidRowLength {
+        index: usize,
+        row: usize,
+        columns: u8,
+    },
+    /// The side to move is not 'W' or 'B'.
+    InvalidTurn { index: usize },
+    /// A PDN piece list does not start with 'W' or 'B'.
+    InvalidColor { index: usize },
+    /// A PDN square is not a number from 1 to 32.
+    InvalidSquare { index: usize },
+    /// A PDN square is listed more than once.
+    OccupiedSquare { index: usize },
+    /// A PDN range like `12-1` ends before it starts.
+    InvalidRange { index: usize },
+    /// The described board is not a valid checkers board.
+    InvalidBoard(BoardError),
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::InvalidCharacter { index, character } => write!(
+                f,
+                "Invalid FEN string. Invalid character '{}' at index {}! Only allowed characters are: 'm', 'M', 'k', 'K', '/', 'W', 'B', '1', '2', '3', '4', '5', '6', '7', '8'",
+                character, index
+            ),
+            FenError::InvalidRowCount { rows } => {
+                write!(f, "Invalid FEN string. Expected 8 rows, found {}.", rows)
+            }
+            FenError::InvalidRowLength {
+                index,
+                row,
+                columns,
+            } => write!(
+                f,
+                "Invalid FEN string. Row {} covers {} columns instead of 8 (at index {}).",
+                row, columns, index
+            ),
+            FenError::InvalidTurn { index } => write!(
+                f,
+                "Invalid FEN string. The side to move at index {} must be 'W' or 'B'.",
+                index
+            ),
+            FenError::InvalidColor { index } => write!(
+                f,
+                "Invalid PDN FEN string. The piece list at index {} must start with 'W' or 'B'.",
+                index
+            ),
+            FenError::InvalidSquare { index } => write!(
+                f,
+                "Invalid PDN FEN string. The square at index {} must be a number from 1 to 32.",
+                index
+            ),
+            FenError::OccupiedSquare { index } => write!(
+                f,
+                "Invalid PDN FEN string. The square at index {} is already occupied.",
+                index
+            ),
+            FenError::InvalidRange { index } => write!(
+                f,
+                "Invalid PDN FEN string. The range at index {} ends before it starts.",
+                index
+            ),
+            FenError::InvalidBoard(err) => write!(f, "Invalid FEN string. {}", err),
+        }
+    }
+}
+
+impl Error for FenError {}
+
+impl Position {
+    /// Parses a position in any of the supported formats.
+    /// Strings containing a ':' are treated as PDN FEN.
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let (board, turn) = Board::parse_fen(fen)?;
+        Ok(Self::from_board(board, turn))
+    }
+
+    pub fn to_fen(&self, format: FenFormat) -> String {
+        match format {
+            FenFormat::Rows => self.board().write_rows(self.turn()),
+            FenFormat::Pdn => self.board().write_pdn(self.turn()),
+        }
+    }
+}
+
+impl Board {
+    /// Parses a board in any of the supported formats, ignoring the side to move.
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        Ok(Self::parse_fen(fen)?.0)
+    }
+
+    /* --------------| Private methods |-------------- */
+    fn parse_fen(fen: &str) -> Result<(Self, PieceColor), FenError> {
+        let trimmed = fen.trim();
+        let (board, turn) = if trimmed.contains(':') {
+            Self::parse_pdn(fen, trimmed)?
+        } else {
+            Self::parse_rows(fen, trimmed)?
+        };
+        board.validate().map_err(FenError::InvalidBoard)?;
+        Ok((board, turn))
+    }
+
+    fn parse_rows(fen: &str, rows: &str) -> Result<(Self, PieceColor), FenError> {
+        let (rows, turn) = match rows.split_once(char::is_whitespace) {
+            Some((rows, turn)) => (rows, Self::parse_turn(fen, turn.trim())?),
+            None => (rows, piece::WHITE),
+        };
+
+        let rows = rows.split('/').collect::<Vec<_>>();
+        return_if!(
+            rows.len() != 8,
+            Err(FenError::InvalidRowCount { rows: rows.len() })
+        );
+
+        let mut board = Self::empty();
+        for (i, row_str) in rows.iter().enumerate() {
+            let row = 7 - i as u8;
+            let mut col = 0u8;
+            for (j, c) in row_str.char_indices() {
+                let index = offset(fen, row_str) + j;
+                let piece = match c {
+                    '1'..='8' => None,
+                    'M' => Some(Piece {
+                        color: piece::WHITE,
+                        piece_type: piece::MAN,
+                    }),
+                    'K' => Some(Piece {
+                        color: piece::WHITE,
+                        piece_type: piece::KING,
+                    }),
+                    'm' => Some(Piece {
+                        color: piece::BLACK,
+                        piece_type: piece::MAN,
+                    }),
+                    'k' => Some(Piece {
+                        color: piece::BLACK,
+                        piece_type: piece::KING,
+                    }),
+                    _ => {
+                        return Err(FenError::InvalidCharacter {
+                            index,
+                            character: c,
+                        })
+                    }
+                };
+                match piece {
+                    Some(piece) => {
+                        if col < 8 {
+                            board.add_piece(&(row * 8 + col), &piece);
+                        }
+                        col += 1;
+                    }
+                    None => col += c as u8 - b'0',
+                }
+                return_if!(
+                    col > 8,
+                    Err(FenError::InvalidRowLength {
+                        index,
+                        row: i + 1,
+                        columns: col,
+                    })
+                );
+            }
+            return_if!(
+                col != 8,
+                Err(FenError::InvalidRowLength {
+                    index: offset(fen, row_str) + row_str.len(),
+                    row: i + 1,
+                    columns: col,
+                })
+            );
+        }
+        Ok((board, turn))
+    }
+
+    fn parse_pdn(fen: &str, pdn: &str) -> Result<(Self, PieceColor), FenError> {
+        let pdn = pdn.strip_suffix('.').unwrap_or(pdn);
+        let mut sections = pdn.split(':');
+        let turn = Self::parse_turn(fen, sections.next().unwrap_or("").trim())?;
+
+        let mut board = Self::empty();
+        for section in sections {
+            let section = section.trim();
+            let color = match section.chars().next() {
+                Some('W') => piece::WHITE,
+                Some('B') => piece::BLACK,
+                _ => {
+                    return Err(FenError::InvalidColor {
+                        index: offset(fen, section),
+                    })
+                }
+            };
+
+            for token in section[1..].split(',').map(str::trim) {
+                if token.is_empty() {
+                    continue;
+                }
+                let (piece_type, squares) = match token.strip_prefix('K') {
+                    Some(squares) => (piece::KING, squares),
+                    None => (piece::MAN, token),
+                };
+                let (first, last) = match squares.split_once('-') {
+                    Some((first, last)) => (
+                        Self::parse_square(fen, first.trim())?,
+                        Self::parse_square(fen, last.trim())?,
+                    ),
+                    None => (
+                        Self::parse_square(fen, squares)?,
+                        Self::parse_square(fen, squares)?,
+                    ),
+                };
+                return_if!(
+                    first > last,
+                    Err(FenError::InvalidRange {
+                        index: offset(fen, token),
+                    })
+                );
+                for square in first..=last {
+                    let id = square_to_id(square).unwrap();
+                    return_if!(
+                        board.get_piece(1 << id).is_some(),
+                        Err(FenError::OccupiedSquare {
+                            index: offset(fen, token),
+                        })
+                    );
+                    board.add_piece(&id, &Piece { color, piece_type });
+                }
+            }
+        }
+        Ok((board, turn))
+    }
+
+    fn parse_turn(fen: &str, turn: &str) -> Result<PieceColor, FenError> {
+        match turn {
+            "W" | "w" => Ok(piece::WHITE),
+            "B" | "b" => Ok(piece::BLACK),
+            _ => Err(FenError::InvalidTurn {
+                index: offset(fen, turn),
+            }),
+        }
+    }
+
+    fn parse_square(fen: &str, square: &str) -> Result<u8, FenError> {
+        // `parse` also accepts a leading '+'
+        let digits = !square.is_empty() && square.bytes().all(|b| b.is_ascii_digit());
+        match square.parse::<u8>() {
+            Ok(n) if digits && (1..=32).contains(&n) => Ok(n),
+            _ => Err(FenError::InvalidSquare {
+                index: offset(fen, square),
+            }),
+        }
+    }
+
+    fn write_rows(&self, turn: PieceColor) -> String {
+        let mut fen = String::new();
+        for row in (0..8).rev() {
+            let mut empty = 0;
+            for col in 0..8 {
+                let c = match self.get_piece(Board::coords_to_bitboard(row, col)) {
+                    Some(piece) => Self::piece_char(&piece),
+                    None => {
+                        empty += 1;
+                        continue;
+                    }
+                };
+                if empty > 0 {
+                    fen.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                fen.push(c);
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if row > 0 {
+                fen.push('/');
+            }
+        }
+        fen.push(' ');
+        fen.push(Self::turn_char(turn));
+        fen
+    }
+
+    fn write_pdn(&self, turn: PieceColor) -> String {
+        let mut fen = String::new();
+        fen.push(Self::turn_char(turn));
+        for color in [piece::WHITE, piece::BLACK] {
+            let squares = (1..=32)
+                .filter_map(|square| {
+                    let piece = self.get_piece(1 << square_to_id(square).unwrap())?;
+                    return_if!(piece.color != color, None);
+                    Some(if piece.piece_type == piece::KING {
+                        format!("K{}", square)
+                    } else {
+                        square.to_string()
+                    })
+                })
+                .collect::<Vec<_>>();
+            fen.push(':');
+            fen.push(Self::turn_char(color));
+            fen.push_str(&squares.join(","));
+        }
+        fen
+    }
+
+    fn piece_char(piece: &Piece) -> char {
+        match (piece.color, piece.piece_type) {
+            (piece::WHITE, piece::MAN) => 'M',
+            (piece::WHITE, piece::KING) => 'K',
+            (piece::BLACK, piece::MAN) => 'm',
+            (piece::BLACK, piece::KING) => 'k',
+        }
+    }
+
+    fn turn_char(color: PieceColor) -> char {
+        if color == piece::WHITE {
+            'W'
+        } else {
+            'B'
+        }
+    }
+}
+
+/// Returns the byte offset of `part` inside `fen`. `part` has to be a slice of `fen`.
+fn offset(fen: &str, part: &str) -> usize {
+    part.as_ptr() as usize - fen.as_ptr() as usize
+}
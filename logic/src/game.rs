@@ -1,52 +1,90 @@
-use crate::board::PossibleMoves;
+use crate::ballot::Ballot;
+use crate::board::Move;
 use crate::player::Player;
-use crate::{board::Board, break_if, piece};
+use crate::position::Position;
+use crate::{break_if, piece};
+
+/// Number of moves per player without a capture or man move after which the game is drawn.
+pub const DEFAULT_NO_PROGRESS_MOVES: u16 = 40;
+
+#[derive(Debug, PartialEq)]
+pub enum DrawReason {
+    /// The same position occurred for the third time with the same side to move.
+    Repetition,
+    /// No capture or man move was made for the configured number of moves.
+    NoProgress,
+}
 
 #[derive(Debug, PartialEq)]
 pub enum GameState {
     InProgress,
     Win(piece::PieceColor),
+    Draw(DrawReason),
 }
 
 pub struct Game {
-    pub board: Board,
-    pub current: piece::PieceColor,
+    pub position: Position,
     pub state: GameState,
     pub player: [Box<dyn Player>; 2],
-    current_player: usize,
+    /// Number of moves per player without a capture or man move after which the game is drawn.
+    /// `None` disables the rule.
+    pub no_progress_moves: Option<u16>,
 }
 
 impl Game {
     /* --------------| Constructors |-------------- */
-    pub fn new(mut player_1: Box<dyn Player>, mut player_2: Box<dyn Player>) -> Self {
+    pub fn new(player_1: Box<dyn Player>, player_2: Box<dyn Player>) -> Self {
+        Self::from_position(Position::new(), player_1, player_2)
+    }
+
+    /// Creates a game continuing from `position`.
+    /// `player_1` plays white and `player_2` plays black.
+    pub fn from_position(
+        position: Position,
+        mut player_1: Box<dyn Player>,
+        mut player_2: Box<dyn Player>,
+    ) -> Self {
         player_1.init(piece::WHITE);
         player_2.init(piece::BLACK);
         Self {
-            board: Board::new(),
-            current: piece::WHITE,
+            position,
             state: GameState::InProgress,
             player: [player_1, player_2],
-            current_player: 0,
+            no_progress_moves: Some(DEFAULT_NO_PROGRESS_MOVES),
         }
     }
 
+    /// Creates a game continuing after the three opening moves of `ballot`.
+    /// `player_1` plays white and `player_2` plays black.
+    pub fn from_ballot(
+        ballot: &Ballot,
+        player_1: Box<dyn Player>,
+        player_2: Box<dyn Player>,
+    ) -> Self {
+        Self::from_position(ballot.position(), player_1, player_2)
+    }
+
     /* --------------| Methods |-------------- */
 
     pub fn play(&mut self) {
         while self.state == GameState::InProgress {
-            let possible_moves = self.board.possible_moves(self.current);
-            break_if!(self.check_game_state(&possible_moves, self.current));
+            let possible_moves = self.position.possible_moves();
+            break_if!(self.check_game_state(&possible_moves, self.position.turn()));
             self.turn(&possible_moves);
-            self.current = !self.current;
         }
 
-        if let GameState::Win(color) = self.state {
-            println!("{} wins!", if color { "White" } else { "Black" })
+        match self.state {
+            GameState::Win(color) => {
+                println!("{} wins!", if color { "White" } else { "Black" })
+            }
+            GameState::Draw(DrawReason::Repetition) => println!("Draw by repetition!"),
+            GameState::Draw(DrawReason::NoProgress) => println!("Draw by the no-progress rule!"),
+            GameState::InProgress => {}
         }
     }
 
     /* --------------| Static methods |-------------- */
-    pub fn is_game_over(possible_moves: &Vec<PossibleMoves>) -> bool {
+    pub fn is_game_over(possible_moves: &[Move]) -> bool {
         if possible_moves.is_empty() {
             return true;
         }
@@ -55,52 +93,40 @@ impl Game {
     }
 
     /* --------------| Private methods |-------------- */
-    fn show(&self, _possible_moves: &[PossibleMoves]) {
-        println!("{}", self.board);
-        // println!("{}", self.board.to_string(&possible_moves));
-        // for (i, (from, moves)) in possible_moves.clone().into_iter().enumerate() {
-        //     print!("{}: {} -> {{\n", i, Self::id_to_chess_notation(from));
-        //     for (j, to) in moves.into_iter().enumerate() {
-        //         print!("    {}: {}, \n", j, Self::id_to_chess_notation(to));
-        //     }
-        //     print!("}}\n");
-        // }
-        // println!();
+    fn show(&self, _possible_moves: &[Move]) {
+        println!("{}", self.position.board());
     }
 
-    fn check_game_state(
-        &mut self,
-        possible_moves: &Vec<PossibleMoves>,
-        color: piece::PieceColor,
-    ) -> bool {
+    fn check_game_state(&mut self, possible_moves: &[Move], color: piece::PieceColor) -> bool {
         if possible_moves.is_empty() {
             self.state = GameState::Win(!color);
             return true;
         }
 
+        if self.position.repetitions() >= 2 {
+            self.state = GameState::Draw(DrawReason::Repetition);
+            return true;
+        }
+
+        if let Some(moves) = self.no_progress_moves {
+            if self.position.halfmove_clock() >= moves * 2 {
+                self.state = GameState::Draw(DrawReason::NoProgress);
+                return true;
+            }
+        }
+
         false
     }
 
-    fn turn(&mut self, possible_moves: &[PossibleMoves]) {
+    fn turn(&mut self, possible_moves: &[Move]) {
         self.show(possible_moves);
-        let (from, to) = self.player[self.current_player].get_move(&self.board, possible_moves);
-        let move_info = self.board.move_piece(&from, &to);
-        // continue to jump if possible
-        if move_info.jumped_piece.is_some() {
-            if let Some(possible_jumps) = self.board.possible_jumps_from(to) {
-                if !possible_jumps.is_empty() {
-                    let possible_moves = PossibleMoves {
-                        from: to,
-                        to: possible_jumps,
-                    };
-                    let possible_moves = vec![possible_moves];
-                    if !self.check_game_state(&possible_moves, self.current) {
-                        self.turn(&possible_moves);
-                    }
-                }
-            }
+        let current = self.position.turn();
+        let current_player = if current == piece::WHITE { 0 } else { 1 };
+        let p_move = self.player[current_player].get_move(&self.position, possible_moves);
+        // A player returning an illegal move forfeits the game
+        if let Err(err) = self.position.make_move(&p_move) {
+            println!("{}: {}", err, p_move);
+            self.state = GameState::Win(!current);
         }
-
-        self.current_player ^= 1;
     }
 }
@@ -1,9 +1,33 @@
+mod ballot;
 mod board;
+mod book;
+mod egdb;
+mod fen;
 mod game;
+mod pdn;
 mod piece;
 mod player;
+mod position;
+mod tune;
 mod util;
+mod zobrist;
 
-pub use board::Board;
-pub use game::Game;
-pub use player::{v1, v2, HumanPlayer, MinimaxPlayer};
+pub use ballot::{play_match, Ballot, BallotError, MatchResult};
+pub use board::{Board, BoardError, Move, MoveError};
+pub use book::{BookError, BookMove, OpeningBook};
+pub use egdb::{
+    EgdbError, EndgameDatabase, Material, Mismatch, Outcome, Table, TableStats, ValidationReport,
+};
+pub use fen::{id_to_square, square_to_id, FenError, FenFormat};
+pub use game::{DrawReason, Game, GameState, DEFAULT_NO_PROGRESS_MOVES};
+pub use pdn::{load_pdn, parse_pdn, PdnError, PdnGame};
+pub use player::{
+    evaluator, win_distance, Contribution, Evaluator, Feature, HumanPlayer, InfoCallback,
+    MinimaxPlayer, Player, PvLine, Score, SearchHandle, SearchLimits, SearchResult, Weights,
+    WeightsError, WinDistance, DEFAULT_TT_SIZE_MB, DRAW_SCORE, EVALUATORS, FEATURE_COUNT,
+    MAN_VALUE, MAX_DEPTH, V1, V2, V3, WIN_SCORE, WIN_THRESHOLD,
+};
+pub use position::Position;
+pub use tune::{
+    load_training_positions, parse_training_positions, TrainingDataError, TrainingPosition, Tuner,
+};
@@ -0,0 +1,266 @@
+use crate::{
+    board::{Board, Move},
+    fen::{id_to_square, FenError},
+    piece,
+    position::Position,
+    return_if,
+};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::Path,
+};
+
+/// Results ending the move text of a game.
+const RESULTS: [&str; 7] = ["1-0", "0-1", "1/2-1/2", "2-0", "0-2", "1-1", "*"];
+
+/// A game read from a PDN file.
+pub struct PdnGame {
+    /// The tags of the game in file order, e.g. `("Event", "World Championship")`.
+    pub tags: Vec<(String, String)>,
+    /// The position before the first move: the `FEN` tag, or the initial board with black to
+    /// move as in standard notation.
+    pub start: Position,
+    pub moves: Vec<Move>,
+}
+
+impl PdnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Reasons for a PDN file to be rejected. Games are counted from 1.
+#[derive(Debug)]
+pub enum PdnError {
+    Io(io::Error),
+    InvalidFen {
+        game: usize,
+        error: FenError,
+    },
+    /// A move is not legal in the position it is played in.
+    IllegalMove {
+        game: usize,
+        notation: String,
+    },
+    /// A shortened capture matches more than one capture route.
+    AmbiguousMove {
+        game: usize,
+        notation: String,
+    },
+}
+
+impl Display for PdnError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PdnError::Io(err) => write!(f, "Could not read the PDN file. {}", err),
+            PdnError::InvalidFen { game, error } => {
+                write!(f, "Invalid FEN tag in game {}. {}", game, error)
+            }
+            PdnError::IllegalMove { game, notation } => {
+                write!(f, "Illegal move '{}' in game {}.", notation, game)
+            }
+            PdnError::AmbiguousMove { game, notation } => write!(
+                f,
+                "Ambiguous move '{}' in game {}. Give every square of the capture.",
+                notation, game
+            ),
+        }
+    }
+}
+
+impl Error for PdnError {}
+
+impl From<io::Error> for PdnError {
+    fn from(err: io::Error) -> Self {
+        PdnError::Io(err)
+    }
+}
+
+/// Parses every game of a PDN file. Comments, variations and move numbers are skipped.
+/// Moves are given as squares (1-32) separated by '-' or 'x', captures may leave out the
+/// squares between the start and the end of the jumps, e.g. `11-15` or `22x8`.
+pub fn parse_pdn(text: &str) -> Result<Vec<PdnGame>, PdnError> {
+    let mut parser = GameParser::default();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => skip_until(&mut chars, '}'),
+            ';' => skip_until(&mut chars, '\n'),
+            '(' => {
+                let mut depth = 1;
+                for c in chars.by_ref() {
+                    depth += (c == '(') as i32 - (c == ')') as i32;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+            }
+            '[' => {
+                let mut tag = String::new();
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    tag.push(c);
+                }
+                parser.tag(&tag);
+            }
+            _ if c.is_whitespace() => {}
+            _ => {
+                let mut token = c.to_string();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"{([;".contains(*c)) {
+                    token.push(c);
+                }
+                parser.token(&token)?;
+            }
+        }
+    }
+    parser.finish()?;
+
+    Ok(parser.games)
+}
+
+pub fn load_pdn(path: impl AsRef<Path>) -> Result<Vec<PdnGame>, PdnError> {
+    parse_pdn(&fs::read_to_string(path)?)
+}
+
+/// Collects the tags and moves of the game being parsed.
+#[derive(Default)]
+struct GameParser {
+    games: Vec<PdnGame>,
+    tags: Vec<(String, String)>,
+    /// The position after the moves so far, set up at the first move.
+    position: Option<Position>,
+    start: Option<Position>,
+    moves: Vec<Move>,
+}
+
+impl GameParser {
+    /// A tag after the move text starts the next game.
+    fn tag(&mut self, tag: &str) {
+        if self.position.is_some() {
+            self.push_game();
+        }
+        let (name, value) = tag
+            .trim()
+            .split_once(char::is_whitespace)
+            .unwrap_or((tag, ""));
+        let value = value.trim().trim_matches('"');
+        self.tags.push((name.to_string(), value.to_string()));
+    }
+
+    fn token(&mut self, token: &str) -> Result<(), PdnError> {
+        if RESULTS.contains(&token) {
+            return self.finish();
+        }
+        // Move numbers like `1.` or `1...`, possibly without a space before the move
+        let token = token.rsplit('.').next().unwrap_or("");
+        let notation = token.trim_end_matches(['!', '?']);
+        return_if!(notation.is_empty(), Ok(()));
+
+        let game = self.games.len() + 1;
+        let position = self.position()?;
+        let p_move = find_move(position, notation).map_err(|err| {
+            let notation = notation.to_string();
+            match err {
+                NotationError::Illegal => PdnError::IllegalMove { game, notation },
+                NotationError::Ambiguous => PdnError::AmbiguousMove { game, notation },
+            }
+        })?;
+        position.move_piece(&p_move);
+        self.moves.push(p_move);
+        Ok(())
+    }
+
+    /// Ends the current game. Results without a game are ignored.
+    fn finish(&mut self) -> Result<(), PdnError> {
+        return_if!(self.tags.is_empty() && self.moves.is_empty(), Ok(()));
+        self.position()?;
+        self.push_game();
+        Ok(())
+    }
+
+    /// Adds the current game once its start position is set up.
+    fn push_game(&mut self) {
+        if let Some(start) = self.start.take() {
+            self.games.push(PdnGame {
+                tags: std::mem::take(&mut self.tags),
+                start,
+                moves: std::mem::take(&mut self.moves),
+            });
+        }
+        self.position = None;
+    }
+
+    fn position(&mut self) -> Result<&mut Position, PdnError> {
+        if self.position.is_none() {
+            let start = match self.tags.iter().find(|(name, _)| name == "FEN") {
+                Some((_, fen)) => {
+                    Position::from_fen(fen).map_err(|error| PdnError::InvalidFen {
+                        game: self.games.len() + 1,
+                        error,
+                    })?
+                }
+                None => Position::from_board(Board::new(), piece::BLACK),
+            };
+            self.start = Some(start.clone());
+            self.position = Some(start);
+        }
+        Ok(self.position.as_mut().unwrap())
+    }
+}
+
+/// Reasons for a move in standard notation not to match a legal move.
+pub(crate) enum NotationError {
+    Illegal,
+    /// A capture given with its first and last square only has several routes.
+    Ambiguous,
+}
+
+/// Returns the legal move matching the notation. A capture may be given with its first and last
+/// square only, as long as a single route connects them.
+pub(crate) fn find_move(position: &Position, notation: &str) -> Result<Move, NotationError> {
+    let squares = notation
+        .split(['-', 'x', 'X'])
+        .map(|square| square.parse::<u8>().ok())
+        .collect::<Option<Vec<_>>>()
+        .ok_or(NotationError::Illegal)?;
+    return_if!(squares.len() < 2, Err(NotationError::Illegal));
+
+    let possible_moves = position.possible_moves();
+    let path = |p_move: &Move| {
+        p_move
+            .path
+            .iter()
+            .map(|id| id_to_square(*id))
+            .collect::<Vec<_>>()
+    };
+    if let Some(p_move) = possible_moves.iter().find(|p_move| path(p_move) == squares) {
+        return Ok(p_move.clone());
+    }
+    return_if!(squares.len() != 2, Err(NotationError::Illegal));
+
+    let mut routes = possible_moves.iter().filter(|p_move| {
+        let path = path(p_move);
+        path[0] == squares[0] && path[path.len() - 1] == squares[1]
+    });
+    match (routes.next(), routes.next()) {
+        (Some(p_move), None) => Ok(p_move.clone()),
+        (Some(_), Some(_)) => Err(NotationError::Ambiguous),
+        _ => Err(NotationError::Illegal),
+    }
+}
+
+fn skip_until(chars: &mut impl Iterator<Item = char>, end: char) {
+    for c in chars.by_ref() {
+        if c == end {
+            break;
+        }
+    }
+}
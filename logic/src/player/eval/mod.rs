@@ -0,0 +1,183 @@
+mod v3;
+mod weights;
+
+use super::search::Score;
+use crate::{piece, position::Position};
+use std::sync::Arc;
+
+pub use v3::{Feature, Weights, FEATURE_COUNT, V3};
+pub use weights::WeightsError;
+
+/// The value of a man in centi-pieces.
+pub const MAN_VALUE: Score = 100;
+
+/// The names of the evaluators available through `evaluator`.
+pub const EVALUATORS: [&str; 3] = ["v1", "v2", "v3"];
+
+/// The part of an evaluation caused by one feature, see `Evaluator::explain`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contribution {
+    pub name: &'static str,
+    /// The difference of the feature between the side to move and its opponent, if countable.
+    pub value: Option<Score>,
+    /// The part of the evaluation in centi-pieces.
+    pub score: Score,
+}
+
+/// Scores positions for the search. Evaluators can hold state like loaded weights, they are
+/// shared between the threads of a search.
+/// Plain functions `fn(&Position) -> Score` are evaluators as well.
+pub trait Evaluator: Send + Sync {
+    /// Returns the value of the position from the point of view of the side to move.
+    fn evaluate(&self, position: &Position) -> Score;
+
+    fn name(&self) -> &str;
+
+    /// Splits the evaluation into the contributions of its features. The scores sum up to the
+    /// value returned by `evaluate`.
+    fn explain(&self, position: &Position) -> Vec<Contribution> {
+        vec![Contribution {
+            name: "total",
+            value: None,
+            score: self.evaluate(position),
+        }]
+    }
+}
+
+impl<F> Evaluator for F
+where
+    F: Fn(&Position) -> Score + Send + Sync,
+{
+    fn evaluate(&self, position: &Position) -> Score {
+        self(position)
+    }
+
+    fn name(&self) -> &str {
+        "custom"
+    }
+}
+
+/// Returns the evaluator called `name`, see `EVALUATORS`.
+pub fn evaluator(name: &str) -> Option<Arc<dyn Evaluator>> {
+    match name {
+        "v1" => Some(Arc::new(V1)),
+        "v2" => Some(Arc::new(V2)),
+        "v3" => Some(Arc::new(V3::default())),
+        _ => None,
+    }
+}
+
+/// Material only evaluation.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct V1;
+
+impl V1 {
+    /// Extra value of a king on top of its value as a piece, in pieces.
+    const KING_MULTIPLIER: Score = 2;
+
+    /// Returns the difference of pieces and of kings between the side to move and its opponent.
+    fn material(position: &Position) -> (Score, Score) {
+        let board = position.board();
+        let color = position.turn();
+        let white_multiplier = if color == piece::WHITE { 1 } else { -1 };
+        let black_multiplier = if color == piece::BLACK { 1 } else { -1 };
+
+        let pieces = board.white_count() as Score * white_multiplier
+            + board.black_count() as Score * black_multiplier;
+        let kings = board.white_king_count() as Score * white_multiplier
+            + board.black_king_count() as Score * black_multiplier;
+        (pieces, kings)
+    }
+}
+
+impl Evaluator for V1 {
+    fn evaluate(&self, position: &Position) -> Score {
+        let (pieces, kings) = Self::material(position);
+        (pieces + kings * Self::KING_MULTIPLIER) * MAN_VALUE
+    }
+
+    fn name(&self) -> &str {
+        "v1"
+    }
+
+    fn explain(&self, position: &Position) -> Vec<Contribution> {
+        let (pieces, kings) = Self::material(position);
+        vec![
+            Contribution {
+                name: "pieces",
+                value: Some(pieces),
+                score: pieces * MAN_VALUE,
+            },
+            Contribution {
+                name: "kings",
+                value: Some(kings),
+                score: kings * Self::KING_MULTIPLIER * MAN_VALUE,
+            },
+        ]
+    }
+}
+
+/// Advancement and king centralisation evaluation.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct V2;
+
+impl V2 {
+    /// Returns the values of the men and of the kings in pieces.
+    fn values(position: &Position) -> (f32, f32) {
+        let board = position.board();
+        let color = position.turn();
+        let (mut men, mut kings) = (0., 0.);
+        let mut all = board.get_white_black();
+        let mut p_count = all.count_ones();
+
+        while p_count > 0 {
+            let id = all.trailing_zeros() as u8;
+            let piece = board.get_piece(1 << id).unwrap();
+            let color_multiplier = if piece.color == color { 1. } else { -1. };
+            if piece.piece_type == piece::KING {
+                kings += color_multiplier
+                    * (3. - (((id / 8) as f32 - 3.5).abs() + ((id % 8) as f32 - 3.5).abs()) / 8.);
+            } else {
+                men += color_multiplier
+                    * match piece.color {
+                        piece::WHITE => (1 + id / 8) as f32 / 8.,
+                        piece::BLACK => (8 - id / 8) as f32 / 8.,
+                    };
+            }
+
+            p_count -= 1;
+            all &= !(1 << id);
+        }
+
+        (men, kings)
+    }
+}
+
+impl Evaluator for V2 {
+    fn evaluate(&self, position: &Position) -> Score {
+        let (men, kings) = Self::values(position);
+        ((men + kings) * MAN_VALUE as f32).round() as Score
+    }
+
+    fn name(&self) -> &str {
+        "v2"
+    }
+
+    fn explain(&self, position: &Position) -> Vec<Contribution> {
+        let (men, _) = Self::values(position);
+        let men = (men * MAN_VALUE as f32).round() as Score;
+        vec![
+            Contribution {
+                name: "men advancement",
+                value: None,
+                score: men,
+            },
+            // Takes the rounding difference so that the scores sum up to the evaluation
+            Contribution {
+                name: "king centralisation",
+                value: None,
+                score: self.evaluate(position) - men,
+            },
+        ]
+    }
+}
@@ -0,0 +1,354 @@
+use super::{Contribution, Evaluator, Score, MAN_VALUE};
+use crate::{
+    board::{Bitboard, Board, DARK_SQUARES},
+    fen::square_to_id,
+    piece::{self, PieceColor},
+    position::Position,
+};
+use std::ops::{Index, IndexMut};
+
+/// Number of features of the v3 evaluation.
+pub const FEATURE_COUNT: usize = 14;
+
+/// Positions with at most this many pieces are scored as endgames.
+const ENDGAME_PIECES: u32 = 8;
+
+const WHITE_BACK_ROW: Bitboard = 0x0000_0000_0000_00FF;
+const BLACK_BACK_ROW: Bitboard = 0xFF00_0000_0000_0000;
+/// The four squares in the middle of the board, 14, 15, 18 and 19.
+const CENTER: Bitboard = squares(&[14, 15, 18, 19]);
+/// The corners with two squares, where a lone king can not be caught by a single king.
+const DOUBLE_CORNERS: Bitboard = squares(&[1, 5, 28, 32]);
+
+/* Formations, indexed by color */
+const BRIDGE: [Bitboard; 2] = [squares(&[1, 3]), squares(&[30, 32])];
+const TRIANGLE: [Bitboard; 2] = [squares(&[2, 3, 7]), squares(&[26, 30, 31])];
+const PYRAMID: [Bitboard; 2] = [
+    squares(&[1, 2, 3, 6, 7, 10]),
+    squares(&[23, 26, 27, 30, 31, 32]),
+];
+/// A man in the dog-hole (5 for white, 28 for black) blocked by an opponent man behind it.
+const DOG_HOLE: [(Bitboard, Bitboard); 2] = [
+    (squares(&[28]), squares(&[32])),
+    (squares(&[5]), squares(&[1])),
+];
+
+/// Squares a man has to pass on any way to the crowning row, indexed by color and id.
+const CONES: [[Bitboard; 64]; 2] = cones();
+
+/// The features of the v3 evaluation. Every feature is counted for both sides and scored as
+/// the difference between the side to move and its opponent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// Number of men.
+    Man,
+    /// Number of kings.
+    King,
+    /// Number of non-capturing moves.
+    Mobility,
+    /// Men guarding the own back rank against promotions.
+    BackRank,
+    /// Men stuck in the dog-hole.
+    DogHole,
+    /// Men on both bridge squares of the back rank.
+    Bridge,
+    /// The triangle formation in front of the back rank.
+    Triangle,
+    /// The pyramid formation of six men.
+    Pyramid,
+    /// Men with a free path to the crowning row.
+    Runaway,
+    /// Sum of the rows the men have advanced.
+    Tempo,
+    /// Pieces on the four center squares.
+    Center,
+    /// Centralisation of the kings.
+    KingCenter,
+    /// Endgame only: kings of the stronger side close to the opponent's pieces.
+    KingChase,
+    /// Endgame only: kings of the weaker side in a double corner.
+    DoubleCorner,
+}
+
+impl Feature {
+    pub const ALL: [Feature; FEATURE_COUNT] = [
+        Feature::Man,
+        Feature::King,
+        Feature::Mobility,
+        Feature::BackRank,
+        Feature::DogHole,
+        Feature::Bridge,
+        Feature::Triangle,
+        Feature::Pyramid,
+        Feature::Runaway,
+        Feature::Tempo,
+        Feature::Center,
+        Feature::KingCenter,
+        Feature::KingChase,
+        Feature::DoubleCorner,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::Man => "man",
+            Feature::King => "king",
+            Feature::Mobility => "mobility",
+            Feature::BackRank => "back_rank",
+            Feature::DogHole => "dog_hole",
+            Feature::Bridge => "bridge",
+            Feature::Triangle => "triangle",
+            Feature::Pyramid => "pyramid",
+            Feature::Runaway => "runaway",
+            Feature::Tempo => "tempo",
+            Feature::Center => "center",
+            Feature::KingCenter => "king_center",
+            Feature::KingChase => "king_chase",
+            Feature::DoubleCorner => "double_corner",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|feature| feature.name() == name)
+    }
+}
+
+/// The value of each feature in centi-pieces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weights([Score; FEATURE_COUNT]);
+
+impl Weights {
+    pub fn new(values: [Score; FEATURE_COUNT]) -> Self {
+        Self(values)
+    }
+
+    /// The weights in the order of `Feature::ALL`.
+    pub fn values(&self) -> &[Score; FEATURE_COUNT] {
+        &self.0
+    }
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self([
+            MAN_VALUE, // Man
+            140,       // King
+            2,         // Mobility
+            6,         // BackRank
+            -12,       // DogHole
+            10,        // Bridge
+            8,         // Triangle
+            10,        // Pyramid
+            35,        // Runaway
+            2,         // Tempo
+            6,         // Center
+            3,         // KingCenter
+            4,         // KingChase
+            12,        // DoubleCorner
+        ])
+    }
+}
+
+impl Index<Feature> for Weights {
+    type Output = Score;
+
+    fn index(&self, feature: Feature) -> &Score {
+        &self.0[feature as usize]
+    }
+}
+
+impl IndexMut<Feature> for Weights {
+    fn index_mut(&mut self, feature: Feature) -> &mut Score {
+        &mut self.0[feature as usize]
+    }
+}
+
+/// Evaluation with classic checkers features and tunable weights.
+#[derive(Debug, Clone, Default)]
+pub struct V3 {
+    weights: Weights,
+}
+
+impl V3 {
+    pub fn new(weights: Weights) -> Self {
+        Self { weights }
+    }
+
+    pub fn weights(&self) -> &Weights {
+        &self.weights
+    }
+
+    /// Returns the feature differences between the side to move and its opponent, in the order
+    /// of `Feature::ALL`. The evaluation is their sum weighted by the `Weights`.
+    pub fn features(&self, position: &Position) -> [Score; FEATURE_COUNT] {
+        let board = position.board();
+        let white = Self::side_features(board, piece::WHITE);
+        let black = Self::side_features(board, piece::BLACK);
+        let sign = if position.turn() == piece::WHITE {
+            1
+        } else {
+            -1
+        };
+        std::array::from_fn(|i| sign * (white[i] - black[i]))
+    }
+
+    /* --------------| Private methods |-------------- */
+    fn side_features(board: &Board, color: PieceColor) -> [Score; FEATURE_COUNT] {
+        let own = board.get_color(color);
+        let opponent = board.get_color(!color);
+        let kings = own & board.get_kings();
+        let men = own & !kings;
+        let occupied = own | opponent;
+        let empty = DARK_SQUARES & !occupied;
+        let side = color as usize;
+
+        let mut features = [0; FEATURE_COUNT];
+        features[Feature::Man as usize] = men.count_ones() as Score;
+        features[Feature::King as usize] = kings.count_ones() as Score;
+        features[Feature::Mobility as usize] =
+            (Self::forward_moves(men, color, empty) + Self::king_moves(kings, empty)) as Score;
+        let back_row = if color == piece::WHITE {
+            WHITE_BACK_ROW
+        } else {
+            BLACK_BACK_ROW
+        };
+        features[Feature::BackRank as usize] = (men & back_row).count_ones() as Score;
+        let (hole, blocker) = DOG_HOLE[side];
+        features[Feature::DogHole as usize] =
+            (men & hole != 0 && opponent & !board.get_kings() & blocker != 0) as Score;
+        features[Feature::Bridge as usize] = (men & BRIDGE[side] == BRIDGE[side]) as Score;
+        features[Feature::Triangle as usize] = (men & TRIANGLE[side] == TRIANGLE[side]) as Score;
+        features[Feature::Pyramid as usize] = (men & PYRAMID[side] == PYRAMID[side]) as Score;
+        features[Feature::Center as usize] = (own & CENTER).count_ones() as Score;
+
+        for id in Self::ids(men) {
+            let row = id / 8;
+            features[Feature::Tempo as usize] +=
+                if color == piece::WHITE { row } else { 7 - row } as Score;
+            features[Feature::Runaway as usize] +=
+                (CONES[side][id as usize] & occupied == 0) as Score;
+        }
+        for id in Self::ids(kings) {
+            let (row, col) = ((id / 8) as Score, (id % 8) as Score);
+            // Distance from the center in half squares, 2 to 14
+            let distance = (2 * row - 7).abs() + (2 * col - 7).abs();
+            features[Feature::KingCenter as usize] += (14 - distance) / 2;
+        }
+
+        if occupied.count_ones() <= ENDGAME_PIECES {
+            let own_count = own.count_ones();
+            let opponent_count = opponent.count_ones();
+            if own_count > opponent_count {
+                for id in Self::ids(kings) {
+                    let closest = Self::ids(opponent)
+                        .map(|other| Self::distance(id, other))
+                        .min()
+                        .unwrap_or(7);
+                    features[Feature::KingChase as usize] += 7 - closest;
+                }
+            } else if own_count < opponent_count {
+                features[Feature::DoubleCorner as usize] =
+                    (kings & DOUBLE_CORNERS).count_ones() as Score;
+            }
+        }
+
+        features
+    }
+
+    /// Counts the non-capturing moves of men. Shifts wrapping around the board land on light
+    /// squares, which are never empty.
+    fn forward_moves(men: Bitboard, color: PieceColor, empty: Bitboard) -> u32 {
+        if color == piece::WHITE {
+            (men << 7 & empty).count_ones() + (men << 9 & empty).count_ones()
+        } else {
+            (men >> 7 & empty).count_ones() + (men >> 9 & empty).count_ones()
+        }
+    }
+
+    fn king_moves(kings: Bitboard, empty: Bitboard) -> u32 {
+        Self::forward_moves(kings, piece::WHITE, empty)
+            + Self::forward_moves(kings, piece::BLACK, empty)
+    }
+
+    /// Number of king moves between two squares on an empty board.
+    fn distance(a: u8, b: u8) -> Score {
+        let rows = (a / 8).abs_diff(b / 8);
+        let cols = (a % 8).abs_diff(b % 8);
+        rows.max(cols) as Score
+    }
+
+    fn ids(mut bitboard: Bitboard) -> impl Iterator<Item = u8> {
+        std::iter::from_fn(move || {
+            if bitboard == 0 {
+                return None;
+            }
+            let id = bitboard.trailing_zeros() as u8;
+            bitboard &= bitboard - 1;
+            Some(id)
+        })
+    }
+}
+
+impl Evaluator for V3 {
+    fn evaluate(&self, position: &Position) -> Score {
+        self.features(position)
+            .iter()
+            .zip(self.weights.values())
+            .map(|(feature, weight)| feature * weight)
+            .sum()
+    }
+
+    fn name(&self) -> &str {
+        "v3"
+    }
+
+    fn explain(&self, position: &Position) -> Vec<Contribution> {
+        Feature::ALL
+            .into_iter()
+            .zip(self.features(position))
+            .map(|(feature, value)| Contribution {
+                name: feature.name(),
+                value: Some(value),
+                score: value * self.weights[feature],
+            })
+            .collect()
+    }
+}
+
+/// Returns a bitboard of the standard squares (1-32).
+const fn squares(list: &[u8]) -> Bitboard {
+    let mut bitboard = 0;
+    let mut i = 0;
+    while i < list.len() {
+        bitboard |= match square_to_id(list[i]) {
+            Some(id) => 1 << id,
+            None => panic!("Invalid square"),
+        };
+        i += 1;
+    }
+    bitboard
+}
+
+/// Computes the squares in front of every square, widening by one column per row.
+const fn cones() -> [[Bitboard; 64]; 2] {
+    let mut cones = [[0; 64]; 2];
+    let mut id = 0;
+    while id < 64 {
+        let (row, col) = ((id / 8) as i32, (id % 8) as i32);
+        let mut other = 0;
+        while other < 64 {
+            let (other_row, other_col) = (other / 8, other % 8);
+            let width = (other_col - col).abs();
+            let bit = 1 << other & DARK_SQUARES;
+            // Index 0 is black, moving down the board
+            if other_row < row && width <= row - other_row {
+                cones[0][id] |= bit;
+            }
+            if other_row > row && width <= other_row - row {
+                cones[1][id] |= bit;
+            }
+            other += 1;
+        }
+        id += 1;
+    }
+    cones
+}
@@ -0,0 +1,111 @@
+use super::{
+    v3::{Feature, Weights, V3},
+    Score,
+};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::Path,
+};
+
+/// Reasons for a weights file to be rejected. Lines are counted from 1.
+#[derive(Debug)]
+pub enum WeightsError {
+    Io(io::Error),
+    /// A line is not of the form `name = value`.
+    InvalidLine {
+        line: usize,
+    },
+    UnknownFeature {
+        line: usize,
+        name: String,
+    },
+    InvalidValue {
+        line: usize,
+    },
+}
+
+impl Display for WeightsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            WeightsError::Io(err) => write!(f, "Could not access the weights file. {}", err),
+            WeightsError::InvalidLine { line } => write!(
+                f,
+                "Invalid weights file. Line {} is not of the form 'name = value'.",
+                line
+            ),
+            WeightsError::UnknownFeature { line, name } => write!(
+                f,
+                "Invalid weights file. Unknown feature '{}' in line {}.",
+                name, line
+            ),
+            WeightsError::InvalidValue { line } => write!(
+                f,
+                "Invalid weights file. The value in line {} must be an integer.",
+                line
+            ),
+        }
+    }
+}
+
+impl Error for WeightsError {}
+
+impl From<io::Error> for WeightsError {
+    fn from(err: io::Error) -> Self {
+        WeightsError::Io(err)
+    }
+}
+
+impl Weights {
+    /// Parses weights written by `Display`: one `name = value` line per feature, '#' starts a
+    /// comment. Features that are not listed keep their default weight.
+    pub fn parse(text: &str) -> Result<Self, WeightsError> {
+        let mut weights = Self::default();
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (name, value) = line
+                .split_once('=')
+                .ok_or(WeightsError::InvalidLine { line: line_number })?;
+            let feature =
+                Feature::from_name(name.trim()).ok_or_else(|| WeightsError::UnknownFeature {
+                    line: line_number,
+                    name: name.trim().to_string(),
+                })?;
+            weights[feature] = value
+                .trim()
+                .parse::<Score>()
+                .map_err(|_| WeightsError::InvalidValue { line: line_number })?;
+        }
+        Ok(weights)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WeightsError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), WeightsError> {
+        Ok(fs::write(path, self.to_string())?)
+    }
+}
+
+impl Display for Weights {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for feature in Feature::ALL {
+            writeln!(f, "{} = {}", feature.name(), self[feature])?;
+        }
+        Ok(())
+    }
+}
+
+impl V3 {
+    /// Creates the evaluation with the weights of a file, see `Weights::parse`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, WeightsError> {
+        Ok(Self::new(Weights::load(path)?))
+    }
+}
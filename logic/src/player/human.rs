@@ -1,4 +1,4 @@
-use super::{Board, Player, PossibleMoves};
+use super::{Move, Player, Position};
 use std::io::*;
 
 pub struct HumanPlayer {
@@ -22,55 +22,29 @@ impl Player for HumanPlayer {
         self.color = color;
     }
 
-    fn get_move(&mut self, _board: &Board, possible_moves: &[PossibleMoves]) -> (u8, u8) {
-        println!("{:?}", possible_moves);
-        let mut input = String::new();
-        let from: u8;
-        let to: u8;
-
-        loop {
-            print!("Select a piece to move [Index]: ");
-            stdout().flush().unwrap();
-            input.clear();
-            stdin().read_line(&mut input).unwrap();
-            match input.trim().parse::<u8>() {
-                Ok(n) => {
-                    if (n as usize) >= possible_moves.len() {
-                        println!("Invalid input!");
-                        continue;
-                    };
-                    from = n;
-                    break;
-                }
-                Err(_) => {
-                    println!("Invalid input!");
-                }
-            };
+    fn get_move(&mut self, _position: &Position, possible_moves: &[Move]) -> Move {
+        for (i, p_move) in possible_moves.iter().enumerate() {
+            println!("{}: {}", i, p_move);
         }
+        let mut input = String::new();
 
         loop {
-            print!("Select a destination [Index]: ");
+            print!("Select a move [Index]: ");
             stdout().flush().unwrap();
             input.clear();
             stdin().read_line(&mut input).unwrap();
-            match input.trim().parse::<u8>() {
+            match input.trim().parse::<usize>() {
                 Ok(n) => {
-                    if (n as usize) >= possible_moves[from as usize].to.len() {
+                    if n >= possible_moves.len() {
                         println!("Invalid input!");
                         continue;
                     };
-                    to = n;
-                    break;
+                    return possible_moves[n].clone();
                 }
                 Err(_) => {
                     println!("Invalid input!");
                 }
             };
         }
-
-        (
-            possible_moves[from as usize].from,
-            possible_moves[from as usize].to[to as usize],
-        )
     }
 }
@@ -1,173 +1,215 @@
-use super::Player;
-use crate::{
-    board::{Board, PossibleMoves},
-    game::Game,
-    piece::{self, PieceColor},
-    return_if, return_if_else,
+use super::{
+    eval::Evaluator,
+    search::{SearchHandle, SearchLimits, SearchResult},
+    tt::{TranspositionTable, DEFAULT_TT_SIZE_MB},
+    worker::{SearchStats, Worker},
+    Player,
 };
-use colored::Colorize;
-use std::collections::HashMap;
+use crate::{board::Move, book::OpeningBook, egdb::EndgameDatabase, position::Position, util::Rng};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Interval in which a finished ponder search checks for a ponderhit or stop.
+const PONDER_WAIT_INTERVAL: Duration = Duration::from_millis(1);
 
-const WIN_BASE_VALUE: f32 = 200.;
+/// Called with the intermediate result after every completed iteration.
+pub type InfoCallback = Box<dyn FnMut(&SearchResult) + Send>;
 
+/// A player using a principal variation search, scoring positions for the side to move.
+/// With more than one thread the search runs as a Lazy SMP: every thread searches the same
+/// position and they share their results through the transposition table.
 pub struct MinimaxPlayer {
-    color: bool,
-    depth: u8,
-    eval: fn(&Board, PieceColor) -> f32,
-    transposition_table: HashMap<Board, f32>,
-    /// The maximum value that can be returned by the evaluation function
-    max_value: f32,
+    limits: SearchLimits,
+    eval: Arc<dyn Evaluator>,
+    /// Kept between searches, see `set_transposition_table_size` to change its size.
+    transposition_table: Arc<TranspositionTable>,
+    endgame_database: Option<Arc<EndgameDatabase>>,
+    /// Moves played without a search while the game is in the book.
+    opening_book: Option<Arc<OpeningBook>>,
+    /// Picks between the book moves.
+    rng: Rng,
+    info_callback: Option<InfoCallback>,
+    /// The search behind the last move returned by `get_move`.
+    last_result: Option<SearchResult>,
+    /// Number of root moves reported with their lines.
+    multi_pv: usize,
+    /// One worker per thread, the first one runs on the calling thread.
+    workers: Vec<Worker>,
+    /// Stops the search from outside. Also tells the helper threads to stop once the main
+    /// thread finished.
+    handle: SearchHandle,
+    helper_nodes: Arc<AtomicU64>,
 }
 
 impl MinimaxPlayer {
-    pub fn new(depth: u8, eval: fn(&Board, PieceColor) -> f32) -> Self {
-        Self {
-            color: piece::WHITE,
-            depth,
+    /// Creates a player searching `depth` plies deep.
+    pub fn new(depth: u8, eval: impl Evaluator + 'static) -> Self {
+        Self::with_limits(SearchLimits::depth(depth), eval)
+    }
+
+    pub fn with_limits(limits: SearchLimits, eval: impl Evaluator + 'static) -> Self {
+        Self::with_evaluator(limits, Arc::new(eval))
+    }
+
+    /// Creates a player with a shared evaluator, e.g. one selected by name with `evaluator`.
+    pub fn with_evaluator(limits: SearchLimits, eval: Arc<dyn Evaluator>) -> Self {
+        let mut player = Self {
+            limits,
             eval,
-            transposition_table: HashMap::new(),
-            max_value: WIN_BASE_VALUE * (depth as f32),
-        }
+            transposition_table: Arc::new(TranspositionTable::new(DEFAULT_TT_SIZE_MB)),
+            endgame_database: None,
+            opening_book: None,
+            rng: Rng::from_time(),
+            info_callback: None,
+            last_result: None,
+            multi_pv: 1,
+            workers: Vec::new(),
+            handle: SearchHandle::default(),
+            helper_nodes: Arc::new(AtomicU64::new(0)),
+        };
+        player.set_threads(1);
+        player
     }
 
-    fn minimax(
-        &mut self,
-        board: &mut Board,
-        depth: u8,
-        maximizing_player: bool,
-        mut alpha: f32,
-        mut beta: f32,
-    ) -> f32 {
-        if let Some(value) = self.transposition_table.get(board) {
-            return *value;
-        }
-        return_if!(depth == 0, (self.eval)(board, self.color));
+    pub fn set_limits(&mut self, limits: SearchLimits) {
+        self.limits = limits;
+    }
 
-        let possible_moves = board.possible_moves(maximizing_player ^ !self.color);
-        let d_max_value = WIN_BASE_VALUE * (depth as f32);
+    /// Sets a function receiving the intermediate result after every completed iteration.
+    pub fn set_info_callback(&mut self, callback: impl FnMut(&SearchResult) + Send + 'static) {
+        self.info_callback = Some(Box::new(callback));
+    }
 
-        if Game::is_game_over(&possible_moves) {
-            // This will favor moves that lead to a win faster
-            return_if_else!(maximizing_player, -d_max_value, d_max_value);
-        }
+    /// Resizes the transposition table to `size_mb` MB and clears it.
+    pub fn set_transposition_table_size(&mut self, size_mb: usize) {
+        self.transposition_table = Arc::new(TranspositionTable::new(size_mb));
+        self.set_threads(self.workers.len());
+    }
 
-        let mut best_value = if maximizing_player {
-            -d_max_value
-        } else {
-            d_max_value
-        };
+    /// Forgets all results of earlier searches, e.g. before starting a new game.
+    pub fn clear_transposition_table(&mut self) {
+        self.transposition_table.clear();
+    }
 
-        for p_move in possible_moves {
-            for to in p_move.to {
-                let move_info = board.move_piece(&p_move.from, &to);
-                let value = self.minimax(
-                    board,
-                    depth - 1,
-                    move_info.jumped_piece.is_some() ^ !maximizing_player,
-                    alpha,
-                    beta,
-                );
-                board.undo_move(move_info);
-                if maximizing_player {
-                    best_value = best_value.max(value);
-                    alpha = alpha.max(value);
-                } else {
-                    best_value = best_value.min(value);
-                    beta = beta.min(value);
-                }
-                if beta <= alpha {
-                    break;
-                }
-            }
-        }
+    /// Scores positions with at most `EndgameDatabase::max_pieces` pieces exactly with the
+    /// endgame database, so that the player plays these endings perfectly.
+    pub fn set_endgame_database(&mut self, database: Arc<EndgameDatabase>) {
+        self.endgame_database = Some(database);
+        self.set_threads(self.workers.len());
+    }
 
-        // TODO: try not to use clone
-        self.transposition_table.insert(board.clone(), best_value);
-        best_value
+    /// Plays the moves of the book instead of searching, picked at random by their weights.
+    /// Only `get_move` uses the book, `analyse` always searches.
+    pub fn set_opening_book(&mut self, book: Arc<OpeningBook>) {
+        self.opening_book = Some(book);
     }
 
-    pub fn analyse(&mut self, board: &Board) {
-        let possible_moves = board.possible_moves(self.color);
+    /// Sets the number of best root moves reported with their lines in `SearchResult::lines`.
+    pub fn set_multi_pv(&mut self, multi_pv: usize) {
+        self.multi_pv = multi_pv.max(1);
+    }
 
-        let mut board_clone = board.clone();
-        let mut best_move = (0, 0);
-        let mut best_value = -self.max_value;
+    /// Sets the number of threads searching in parallel. A single thread searches
+    /// deterministically, more threads usually reach greater depths but the results can vary
+    /// between runs.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.workers = (0..threads.max(1))
+            .map(|id| {
+                Worker::new(
+                    id,
+                    Arc::clone(&self.eval),
+                    Arc::clone(&self.transposition_table),
+                    self.endgame_database.clone(),
+                    self.handle.clone(),
+                    Arc::clone(&self.helper_nodes),
+                )
+            })
+            .collect();
+    }
 
-        self.transposition_table.clear();
-        for p_move in &possible_moves {
-            for to in &p_move.to {
-                let move_info = board_clone.move_piece(&p_move.from, to);
-                let value = self.minimax(
-                    &mut board_clone,
-                    self.depth,
-                    false,
-                    -self.max_value,
-                    self.max_value,
-                );
-                println!(
-                    "{}",
-                    format!(
-                        "({} -> {}) | {}",
-                        p_move.from,
-                        *to,
-                        value.to_string().dimmed()
-                    )
-                    .white()
-                );
-                board_clone.undo_move(move_info);
-                if value > best_value {
-                    best_value = value;
-                    best_move = (p_move.from, *to);
-                }
-            }
-        }
+    /// Returns the result of the search behind the last move of `get_move`, `None` if that move
+    /// came from the opening book.
+    pub fn last_result(&self) -> Option<&SearchResult> {
+        self.last_result.as_ref()
+    }
 
-        println!(
-            "{}",
-            format!(
-                "Best move: ({} -> {}) | {}",
-                best_move.0, best_move.1, best_value
-            )
-            .green()
-            .bold()
-        );
+    /// Returns a handle to stop the searches of this player from another thread.
+    pub fn handle(&self) -> SearchHandle {
+        self.handle.clone()
     }
-}
 
-impl Player for MinimaxPlayer {
-    fn init(&mut self, color: bool) {
-        self.color = color;
+    /// Searches the position for the side to move.
+    pub fn analyse(&mut self, position: &Position) -> SearchResult {
+        self.search(position, false)
     }
 
-    fn get_move(&mut self, board: &Board, possible_moves: &[PossibleMoves]) -> (u8, u8) {
-        let mut board_clone = board.clone();
-        let mut best_move = (0, 0);
-        let mut best_value = -self.max_value;
+    /// Searches on the opponent's time, usually the position after the expected reply.
+    /// The search ignores its limits and only returns once `SearchHandle::ponderhit` or
+    /// `SearchHandle::stop` is called. After a ponderhit it continues as a normal search with
+    /// the time limit starting at the ponderhit; a stopped ponder search is usually discarded.
+    pub fn ponder(&mut self, position: &Position) -> SearchResult {
+        self.search(position, true)
+    }
 
-        self.transposition_table.clear();
-        for p_move in possible_moves {
-            for to in &p_move.to {
-                let move_info = board_clone.move_piece(&p_move.from, to);
-                let value = self.minimax(
-                    &mut board_clone,
-                    self.depth,
-                    false,
-                    -self.max_value,
-                    self.max_value,
-                );
-                println!("({} -> {}) | {}", p_move.from, *to, value);
-                board_clone.undo_move(move_info);
-                if value > best_value {
-                    best_value = value;
-                    best_move = (p_move.from, *to);
-                }
+    /// Searches with all threads until the main thread finished.
+    fn search(&mut self, position: &Position, ponder: bool) -> SearchResult {
+        self.helper_nodes.store(0, Ordering::Relaxed);
+        self.transposition_table.new_search();
+
+        let (limits, multi_pv) = (self.limits, self.multi_pv);
+        let handle = &self.handle;
+        let info_callback = self.info_callback.as_mut();
+        let (main, helpers) = self.workers.split_first_mut().unwrap();
+
+        let mut result = thread::scope(|scope| {
+            for helper in helpers.iter_mut() {
+                scope.spawn(move || helper.search(position, limits, 1, ponder, None));
+            }
+            let result = main.search(position, limits, multi_pv, ponder, info_callback);
+            // A ponder search must not return before the opponent moved
+            while ponder && !handle.is_ponderhit() && !handle.is_stopped() {
+                thread::sleep(PONDER_WAIT_INTERVAL);
             }
+            handle.stop();
+            result
+        });
+        self.handle.reset();
+
+        // Statistics of all threads
+        let mut stats = SearchStats::default();
+        for worker in &self.workers {
+            stats.add(&worker.stats);
         }
+        let elapsed = result.elapsed;
+        stats.report(&mut result, elapsed);
+        result
+    }
+}
 
-        println!(
-            "Best move: ({} -> {}) | {}",
-            best_move.0, best_move.1, best_value
-        );
+impl Player for MinimaxPlayer {
+    fn init(&mut self, _color: bool) {}
+
+    fn get_move(&mut self, position: &Position, possible_moves: &[Move]) -> Move {
+        self.last_result = None;
+        if let Some(book_move) = self
+            .opening_book
+            .as_ref()
+            .and_then(|book| book.pick(position, &mut self.rng))
+        {
+            return book_move;
+        }
+        let result = self.search(position, false);
+        let best_move = result
+            .best_move
+            .clone()
+            .unwrap_or_else(|| possible_moves[0].clone());
+        self.last_result = Some(result);
         best_move
     }
 }
@@ -1,14 +1,25 @@
 pub mod eval;
 mod human;
 mod minimax;
+mod search;
+mod tt;
+mod worker;
 
-pub use eval::{v1, v2};
+pub use eval::{
+    evaluator, Contribution, Evaluator, Feature, Weights, WeightsError, EVALUATORS, FEATURE_COUNT,
+    MAN_VALUE, V1, V2, V3,
+};
 pub use human::HumanPlayer;
-pub use minimax::MinimaxPlayer;
+pub use minimax::{InfoCallback, MinimaxPlayer};
+pub use search::{
+    win_distance, PvLine, Score, SearchHandle, SearchLimits, SearchResult, WinDistance, DRAW_SCORE,
+    MAX_DEPTH, WIN_SCORE, WIN_THRESHOLD,
+};
+pub use tt::DEFAULT_TT_SIZE_MB;
 
-use crate::board::{Board, PossibleMoves};
+use crate::{board::Move, position::Position};
 
 pub trait Player {
     fn init(&mut self, color: bool);
-    fn get_move(&mut self, board: &Board, possible_moves: &[PossibleMoves]) -> (u8, u8);
+    fn get_move(&mut self, position: &Position, possible_moves: &[Move]) -> Move;
 }
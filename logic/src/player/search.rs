@@ -0,0 +1,152 @@
+use crate::{board::Move, return_if};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// The deepest iteration a search can reach.
+pub const MAX_DEPTH: u8 = 64;
+
+/// Search and evaluation scores in centi-pieces, a man is worth 100.
+/// Scores are from the point of view of the side to move.
+pub type Score = i32;
+
+pub const DRAW_SCORE: Score = 0;
+
+/// Score of a won position. A win in n plies scores `WIN_SCORE - n`, a loss `-WIN_SCORE + n`.
+pub const WIN_SCORE: Score = 30_000;
+
+/// Scores at least this far from zero are wins or losses in a known number of plies.
+pub const WIN_THRESHOLD: Score = WIN_SCORE - 1_000;
+
+/// The number of plies until the game ends for the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WinDistance {
+    Win(u32),
+    /// `Loss(0)` is a position without legal moves.
+    Loss(u32),
+}
+
+/// Returns the number of plies until the game ends for a win or loss score, `None` for other
+/// scores.
+pub fn win_distance(score: Score) -> Option<WinDistance> {
+    return_if!(score.abs() > WIN_SCORE, None);
+    if score >= WIN_THRESHOLD {
+        Some(WinDistance::Win((WIN_SCORE - score) as u32))
+    } else if score <= -WIN_THRESHOLD {
+        Some(WinDistance::Loss((WIN_SCORE + score) as u32))
+    } else {
+        None
+    }
+}
+
+/// Limits of an iterative deepening search. The search stops at whichever limit is hit first and
+/// returns the result of the last completed iteration. The first iteration always completes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchLimits {
+    /// The deepest iteration in plies.
+    pub depth: u8,
+    pub time: Option<Duration>,
+    pub nodes: Option<u64>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u8) -> Self {
+        Self {
+            depth: depth.clamp(1, MAX_DEPTH),
+            time: None,
+            nodes: None,
+        }
+    }
+
+    pub fn time(time: Duration) -> Self {
+        Self {
+            depth: MAX_DEPTH,
+            time: Some(time),
+            nodes: None,
+        }
+    }
+
+    pub fn nodes(nodes: u64) -> Self {
+        Self {
+            depth: MAX_DEPTH,
+            time: None,
+            nodes: Some(nodes),
+        }
+    }
+}
+
+/// Controls a search running on another thread. Clones control the same searches.
+#[derive(Debug, Clone, Default)]
+pub struct SearchHandle {
+    stop: Arc<AtomicBool>,
+    ponderhit: Arc<AtomicBool>,
+}
+
+impl SearchHandle {
+    /// Stops the running search, which returns the result of its last completed iteration.
+    /// The first iteration always completes. A stop sent while no search is running stops the
+    /// next search.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// Turns a running ponder search into a normal search: the search limits apply from now on.
+    pub fn ponderhit(&self) {
+        self.ponderhit.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn is_ponderhit(&self) -> bool {
+        self.ponderhit.load(Ordering::Relaxed)
+    }
+
+    /// Clears the signals once a search returned.
+    pub(crate) fn reset(&self) {
+        self.stop.store(false, Ordering::Relaxed);
+        self.ponderhit.store(false, Ordering::Relaxed);
+    }
+}
+
+/// A root move with its value and the expected line of play starting with it.
+#[derive(Debug, Clone)]
+pub struct PvLine {
+    pub score: Score,
+    pub pv: Vec<Move>,
+}
+
+/// The outcome of a (possibly still running) search.
+/// Values are from the point of view of the side to move at the root.
+#[derive(Debug, Clone, Default)]
+pub struct SearchResult {
+    /// `None` if the side to move has no legal move.
+    pub best_move: Option<Move>,
+    pub score: Score,
+    /// The expected line of play, starting with the best move.
+    pub pv: Vec<Move>,
+    /// The best root moves with their lines, best first. Holds up to `multi_pv` lines.
+    pub lines: Vec<PvLine>,
+    /// Every root move with its value, best first.
+    /// Only the values of the first `multi_pv` moves are exact, the others are upper bounds.
+    pub root_moves: Vec<(Move, Score)>,
+    /// The depth of the last completed iteration. Without a completed iteration, e.g. with a
+    /// depth limit of 0, the scores are `DRAW_SCORE`.
+    pub depth: u8,
+    pub nodes: u64,
+    pub nodes_per_second: u64,
+    /// Fraction of transposition table probes that found an entry.
+    pub tt_hit_rate: f32,
+    /// Number of beta cutoffs.
+    pub cutoffs: u64,
+    /// Fraction of beta cutoffs caused by the first searched move, a measure of move ordering.
+    pub first_move_cutoff_rate: f32,
+    /// Number of positions scored by the endgame database.
+    pub egdb_hits: u64,
+    pub elapsed: Duration,
+}
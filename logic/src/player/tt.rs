@@ -0,0 +1,198 @@
+use super::search::{Score, WIN_SCORE};
+use std::{
+    mem::size_of,
+    sync::atomic::{AtomicU64, AtomicU8, Ordering},
+};
+
+/// Default transposition table size in MB.
+pub const DEFAULT_TT_SIZE_MB: usize = 16;
+
+/// Entries sharing one index. A store replaces the least valuable entry of the bucket.
+const BUCKET_SIZE: usize = 2;
+
+/// How a stored value relates to the real value of the position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// The value is exact.
+    Exact,
+    /// The search failed high, the real value is at least the stored one.
+    Lower,
+    /// The search failed low, the real value is at most the stored one.
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TTEntry {
+    /// Zobrist key of the stored position. 0 marks an empty entry.
+    pub key: u64,
+    /// The value from the point of view of the side to move.
+    /// Win and loss scores count the plies from the stored position, not from the root.
+    pub value: Score,
+    /// The remaining search depth the value was computed with.
+    pub depth: u8,
+    pub bound: Bound,
+    /// Index of the best move in the list returned by `Position::possible_moves`.
+    pub best_move: Option<u8>,
+    /// The search generation that stored the entry.
+    pub age: u8,
+}
+
+impl Default for TTEntry {
+    fn default() -> Self {
+        Self {
+            key: 0,
+            value: 0,
+            depth: 0,
+            bound: Bound::Exact,
+            best_move: None,
+            age: 0,
+        }
+    }
+}
+
+impl TTEntry {
+    /// Packs everything but the key into one word.
+    fn pack(&self) -> u64 {
+        (self.value as i16 as u16 as u64)
+            | (self.depth as u64) << 16
+            | (self.bound as u64) << 24
+            | (self.best_move.unwrap_or(NO_MOVE) as u64) << 26
+            | (self.age as u64) << 34
+    }
+
+    fn unpack(key: u64, data: u64) -> Self {
+        Self {
+            key,
+            value: data as u16 as i16 as Score,
+            depth: (data >> 16) as u8,
+            bound: match (data >> 24) & 0b11 {
+                0 => Bound::Exact,
+                1 => Bound::Lower,
+                _ => Bound::Upper,
+            },
+            best_move: Some((data >> 26) as u8).filter(|index| *index != NO_MOVE),
+            age: (data >> 34) as u8,
+        }
+    }
+}
+
+/// Packed best move of an entry without one.
+const NO_MOVE: u8 = u8::MAX;
+
+/// An entry as stored in the table. The key is stored XORed with the data, so that an entry torn
+/// by concurrent writes of two threads no longer matches the key of either position.
+#[derive(Default)]
+struct Slot {
+    check: AtomicU64,
+    data: AtomicU64,
+}
+
+impl Slot {
+    fn load(&self) -> TTEntry {
+        let data = self.data.load(Ordering::Relaxed);
+        let key = self.check.load(Ordering::Relaxed) ^ data;
+        TTEntry::unpack(key, data)
+    }
+
+    fn save(&self, entry: &TTEntry) {
+        let data = entry.pack();
+        self.check.store(entry.key ^ data, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+    }
+}
+
+/// A fixed-size hash table of search results indexed by Zobrist key.
+/// Entries persist between searches; every search increments the age so that stale entries are
+/// replaced first.
+/// The table is lock-free and can be shared between the threads of a parallel search.
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+    /// Mask turning a key into the index of the first entry of its bucket.
+    mask: usize,
+    age: AtomicU8,
+}
+
+impl TranspositionTable {
+    /* --------------| Constructors |-------------- */
+    pub fn new(size_mb: usize) -> Self {
+        let buckets = Self::bucket_count(size_mb);
+        Self {
+            slots: (0..buckets * BUCKET_SIZE)
+                .map(|_| Slot::default())
+                .collect(),
+            mask: (buckets - 1) * BUCKET_SIZE,
+            age: AtomicU8::new(0),
+        }
+    }
+
+    /* --------------| Methods |-------------- */
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot.save(&TTEntry::default());
+        }
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    /// Marks the start of a new search. Entries of earlier searches are replaced first.
+    pub fn new_search(&self) {
+        self.age.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn probe(&self, key: u64) -> Option<TTEntry> {
+        let index = key as usize & self.mask;
+        self.slots[index..index + BUCKET_SIZE]
+            .iter()
+            .map(Slot::load)
+            .find(|entry| entry.key == key)
+    }
+
+    pub fn store(&self, key: u64, value: Score, depth: u8, bound: Bound, best_move: Option<u8>) {
+        debug_assert!(
+            value.abs() <= WIN_SCORE,
+            "score {} does not fit the entry",
+            value
+        );
+        let index = key as usize & self.mask;
+        let age = self.age.load(Ordering::Relaxed);
+        let slots = &self.slots[index..index + BUCKET_SIZE];
+        let bucket: [TTEntry; BUCKET_SIZE] = std::array::from_fn(|slot| slots[slot].load());
+
+        // Reuse the entry of the same position, otherwise replace the least valuable one
+        let slot = match bucket.iter().position(|entry| entry.key == key) {
+            Some(slot) => {
+                // Keep a deeper result of the current search unless the new one is exact
+                let entry = &bucket[slot];
+                if entry.age == age && entry.depth > depth && bound != Bound::Exact {
+                    return;
+                }
+                slot
+            }
+            None => (0..BUCKET_SIZE)
+                .min_by_key(|slot| {
+                    let entry = &bucket[*slot];
+                    (entry.key != 0, entry.age == age, entry.depth)
+                })
+                .unwrap(),
+        };
+
+        slots[slot].save(&TTEntry {
+            key,
+            value,
+            depth,
+            bound,
+            best_move: best_move.or(if bucket[slot].key == key {
+                bucket[slot].best_move
+            } else {
+                None
+            }),
+            age,
+        });
+    }
+
+    /* --------------| Private methods |-------------- */
+    fn bucket_count(size_mb: usize) -> usize {
+        let buckets = (size_mb.max(1) << 20) / (size_of::<Slot>() * BUCKET_SIZE);
+        // Round down to a power of two so that the index is a simple mask
+        1 << (usize::BITS - 1 - buckets.leading_zeros())
+    }
+}
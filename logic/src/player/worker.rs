@@ -0,0 +1,632 @@
+use super::{
+    eval::Evaluator,
+    search::{
+        PvLine, Score, SearchHandle, SearchLimits, SearchResult, DRAW_SCORE, MAX_DEPTH, WIN_SCORE,
+        WIN_THRESHOLD,
+    },
+    tt::{Bound, TranspositionTable},
+    InfoCallback,
+};
+use crate::{
+    board::Move,
+    break_if,
+    egdb::{EndgameDatabase, Outcome},
+    game::Game,
+    piece::PieceColor,
+    position::Position,
+    return_if,
+};
+use std::{
+    cmp::Reverse,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Bound of the search window, beyond any reachable score.
+const INFINITY: Score = WIN_SCORE + 1;
+/// Number of nodes between two checks of the time limit.
+const TIME_CHECK_INTERVAL: u64 = 1024;
+/// Number of plies from the root with killer move slots.
+const MAX_PLY: usize = 128;
+/// Number of killer moves kept per ply.
+const KILLER_SLOTS: usize = 2;
+/// First iteration searched with an aspiration window instead of the full window.
+const ASPIRATION_DEPTH: u8 = 4;
+/// Distance of the aspiration window bounds from the previous score, doubled on every failure.
+const ASPIRATION_DELTA: Score = 25;
+
+/* Move ordering priorities, higher is searched first */
+const TT_MOVE_PRIORITY: u32 = u32::MAX;
+const CAPTURE_PRIORITY: u32 = 1 << 30;
+const KILLER_PRIORITY: u32 = 1 << 29;
+
+/// Outcome of searching the root moves at one depth.
+enum Iteration {
+    /// The lines of all root moves in search order.
+    Completed(Vec<PvLine>),
+    /// The best score is below the aspiration window.
+    FailLow,
+    /// A score is above the aspiration window.
+    FailHigh,
+    /// A search limit was hit.
+    Stopped,
+}
+
+/// Counters of one search.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchStats {
+    pub nodes: u64,
+    pub tt_probes: u64,
+    pub tt_hits: u64,
+    pub cutoffs: u64,
+    pub first_move_cutoffs: u64,
+    pub egdb_hits: u64,
+}
+
+impl SearchStats {
+    pub fn add(&mut self, other: &SearchStats) {
+        self.nodes += other.nodes;
+        self.tt_probes += other.tt_probes;
+        self.tt_hits += other.tt_hits;
+        self.cutoffs += other.cutoffs;
+        self.first_move_cutoffs += other.first_move_cutoffs;
+        self.egdb_hits += other.egdb_hits;
+    }
+
+    /// Writes the statistics of a search running for `elapsed` into `result`.
+    pub fn report(&self, result: &mut SearchResult, elapsed: Duration) {
+        result.nodes = self.nodes;
+        result.nodes_per_second = (self.nodes as f64 / elapsed.as_secs_f64().max(1e-9)) as u64;
+        result.tt_hit_rate = if self.tt_probes == 0 {
+            0.
+        } else {
+            self.tt_hits as f32 / self.tt_probes as f32
+        };
+        result.cutoffs = self.cutoffs;
+        result.first_move_cutoff_rate = if self.cutoffs == 0 {
+            0.
+        } else {
+            self.first_move_cutoffs as f32 / self.cutoffs as f32
+        };
+        result.egdb_hits = self.egdb_hits;
+        result.elapsed = elapsed;
+    }
+}
+
+/// One thread of a search. All threads share the transposition table; the main thread (id 0)
+/// enforces the limits and tells the helper threads to stop through the shared search handle.
+pub struct Worker {
+    id: usize,
+    limits: SearchLimits,
+    eval: Arc<dyn Evaluator>,
+    multi_pv: usize,
+    transposition_table: Arc<TranspositionTable>,
+    /// Exact scores for positions with few pieces.
+    endgame_database: Option<Arc<EndgameDatabase>>,
+    handle: SearchHandle,
+    /// Nodes searched by the helper threads, used for the node limit of the main thread.
+    helper_nodes: Arc<AtomicU64>,
+    /// Quiet moves that caused a cutoff, per ply from the root.
+    killers: Vec<[Option<Move>; KILLER_SLOTS]>,
+    /// Cutoff scores of quiet moves indexed by color, from and to.
+    history: Box<[[[u32; 64]; 64]; 2]>,
+
+    /* Search state */
+    start: Instant,
+    /// Set while searching on the opponent's time, the limits do not apply until a ponderhit.
+    pondering: bool,
+    /// Start of the time limit, the ponderhit for a ponder search.
+    limit_start: Instant,
+    pub stats: SearchStats,
+    completed_depth: u8,
+    /// Set when a limit is hit. The running iteration is discarded.
+    stopped: bool,
+}
+
+impl Worker {
+    pub fn new(
+        id: usize,
+        eval: Arc<dyn Evaluator>,
+        transposition_table: Arc<TranspositionTable>,
+        endgame_database: Option<Arc<EndgameDatabase>>,
+        handle: SearchHandle,
+        helper_nodes: Arc<AtomicU64>,
+    ) -> Self {
+        Self {
+            id,
+            limits: SearchLimits::depth(1),
+            eval,
+            multi_pv: 1,
+            transposition_table,
+            endgame_database,
+            handle,
+            helper_nodes,
+            killers: vec![[None, None]; MAX_PLY],
+            history: Box::new([[[0; 64]; 64]; 2]),
+            start: Instant::now(),
+            pondering: false,
+            limit_start: Instant::now(),
+            stats: SearchStats::default(),
+            completed_depth: 0,
+            stopped: false,
+        }
+    }
+
+    /// Searches every move of the side to move with iterative deepening.
+    /// Only the main thread reports intermediate results to `info_callback`.
+    /// A ponder search ignores the limits until a ponderhit.
+    pub fn search(
+        &mut self,
+        position: &Position,
+        limits: SearchLimits,
+        multi_pv: usize,
+        ponder: bool,
+        mut info_callback: Option<&mut InfoCallback>,
+    ) -> SearchResult {
+        self.limits = limits;
+        self.multi_pv = multi_pv;
+        self.start = Instant::now();
+        self.pondering = ponder && !self.handle.is_ponderhit();
+        self.limit_start = self.start;
+        self.stats = SearchStats::default();
+        self.completed_depth = 0;
+        self.stopped = false;
+        self.killers.fill([None, None]);
+        // Older history scores count less in the new search
+        for score in self.history.iter_mut().flatten().flatten() {
+            *score /= 2;
+        }
+
+        let mut position = position.clone();
+        let mut lines = position
+            .possible_moves()
+            .into_iter()
+            .map(|p_move| PvLine {
+                score: -INFINITY,
+                pv: vec![p_move],
+            })
+            .collect::<Vec<_>>();
+        let mut result = self.result(lines.clone());
+
+        // Helpers start at different depths so that the threads search different trees
+        let first_depth = 1 + (self.id % 2) as u8;
+        'deepening: for depth in first_depth..=MAX_DEPTH {
+            break_if!(depth > self.limits.depth && !self.pondering);
+            // The window is centered on the previous score, which only exists for a single line
+            let mut delta = ASPIRATION_DELTA;
+            let (mut alpha, mut beta) = match lines.first() {
+                Some(line) if depth >= ASPIRATION_DEPTH && self.multi_pv == 1 => (
+                    (line.score - delta).max(-INFINITY),
+                    (line.score + delta).min(INFINITY),
+                ),
+                _ => (-INFINITY, INFINITY),
+            };
+
+            let mut iteration = loop {
+                match self.search_root(&mut position, depth, &lines, alpha, beta) {
+                    Iteration::Completed(iteration) => break iteration,
+                    Iteration::FailLow => alpha = (alpha - delta).max(-INFINITY),
+                    Iteration::FailHigh => beta = (beta + delta).min(INFINITY),
+                    Iteration::Stopped => break 'deepening,
+                }
+                delta *= 2;
+            };
+
+            iteration.sort_by_key(|line| Reverse(line.score));
+            lines = iteration;
+            self.completed_depth = depth;
+
+            result = self.result(lines.clone());
+            if let Some(callback) = info_callback.as_mut() {
+                callback(&result);
+            }
+        }
+
+        // Statistics include the interrupted iteration
+        self.stats.report(&mut result, self.start.elapsed());
+        result
+    }
+
+    /// Returns the score of the position for the side to move.
+    /// `pv` receives the expected line of play from this position.
+    fn negamax(
+        &mut self,
+        position: &mut Position,
+        depth: u8,
+        mut alpha: Score,
+        beta: Score,
+        ply: usize,
+        pv: &mut Vec<Move>,
+    ) -> Score {
+        if depth == 0 {
+            return self.quiescence(position, alpha, beta, ply, pv);
+        }
+        pv.clear();
+        self.stats.nodes += 1;
+        return_if!(self.should_stop(), DRAW_SCORE);
+        // A repetition inside the search can be repeated again, so it is scored as a draw
+        return_if!(position.repetitions() > 0, DRAW_SCORE);
+        if let Some(score) = self.probe_endgame_database(position, ply) {
+            return score;
+        }
+
+        let key = position.key();
+        let tt_entry = self.transposition_table.probe(key);
+        self.stats.tt_probes += 1;
+        self.stats.tt_hits += tt_entry.is_some() as u64;
+        // Cutoffs are only taken in null window nodes to keep the principal variation intact
+        let null_window = beta - alpha == 1;
+        if let Some(entry) = tt_entry.filter(|entry| null_window && entry.depth >= depth) {
+            let value = Self::value_from_tt(entry.value, ply);
+            match entry.bound {
+                Bound::Exact => return value,
+                Bound::Lower if value >= beta => return value,
+                Bound::Upper if value <= alpha => return value,
+                _ => {}
+            }
+        }
+
+        let possible_moves = position.possible_moves();
+        if Game::is_game_over(&possible_moves) {
+            // This will favor moves that lead to a win faster
+            return -(WIN_SCORE - ply as Score);
+        }
+
+        let tt_move = tt_entry.and_then(|entry| entry.best_move);
+        let order = self.order_moves(&possible_moves, tt_move, ply, position.turn());
+
+        let alpha_start = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = order[0];
+        let mut line = Vec::new();
+
+        for (n, i) in order.into_iter().enumerate() {
+            let p_move = &possible_moves[i];
+            position.move_piece(p_move);
+            let score = if n == 0 {
+                -self.negamax(position, depth - 1, -beta, -alpha, ply + 1, &mut line)
+            } else {
+                // Try to prove that the move is not better than the best one so far
+                let score =
+                    -self.negamax(position, depth - 1, -alpha - 1, -alpha, ply + 1, &mut line);
+                if score > alpha && score < beta && !self.stopped {
+                    -self.negamax(position, depth - 1, -beta, -alpha, ply + 1, &mut line)
+                } else {
+                    score
+                }
+            };
+            position.unmake_move(p_move);
+            // The scores of an interrupted search are meaningless
+            return_if!(self.stopped, DRAW_SCORE);
+
+            if score > best_score {
+                best_score = score;
+                best_move = i;
+                if score > alpha {
+                    alpha = score;
+                    pv.clear();
+                    pv.push(p_move.clone());
+                    pv.append(&mut line);
+                }
+            }
+            if alpha >= beta {
+                self.stats.cutoffs += 1;
+                self.stats.first_move_cutoffs += (n == 0) as u64;
+                if !p_move.is_capture() {
+                    self.update_quiet_cutoff(p_move, ply, depth, position.turn());
+                }
+                break;
+            }
+        }
+
+        let bound = if best_score <= alpha_start {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.transposition_table.store(
+            key,
+            Self::value_to_tt(best_score, ply),
+            depth,
+            bound,
+            Some(best_move as u8),
+        );
+        best_score
+    }
+
+    /// Resolves pending captures before evaluating, so that the evaluation is never called in
+    /// the middle of an exchange. Captures are mandatory, so there is no stand pat while the side
+    /// to move can capture.
+    fn quiescence(
+        &mut self,
+        position: &mut Position,
+        mut alpha: Score,
+        beta: Score,
+        ply: usize,
+        pv: &mut Vec<Move>,
+    ) -> Score {
+        pv.clear();
+        self.stats.nodes += 1;
+        return_if!(self.should_stop(), DRAW_SCORE);
+        return_if!(position.repetitions() > 0, DRAW_SCORE);
+        if let Some(score) = self.probe_endgame_database(position, ply) {
+            return score;
+        }
+
+        let possible_moves = position.possible_moves();
+        if Game::is_game_over(&possible_moves) {
+            return -(WIN_SCORE - ply as Score);
+        }
+        return_if!(!possible_moves[0].is_capture(), self.evaluate(position));
+
+        let order = self.order_moves(&possible_moves, None, ply, position.turn());
+        let mut best_score = -INFINITY;
+        let mut line = Vec::new();
+
+        for i in order {
+            let p_move = &possible_moves[i];
+            position.move_piece(p_move);
+            let score = -self.quiescence(position, -beta, -alpha, ply + 1, &mut line);
+            position.unmake_move(p_move);
+            return_if!(self.stopped, DRAW_SCORE);
+
+            if score > best_score {
+                best_score = score;
+                if score > alpha {
+                    alpha = score;
+                    pv.clear();
+                    pv.push(p_move.clone());
+                    pv.append(&mut line);
+                }
+            }
+            break_if!(alpha >= beta);
+        }
+
+        best_score
+    }
+
+    /// Evaluations are kept below `WIN_THRESHOLD`, so that custom evaluators or tuned weights
+    /// are never mistaken for a forced win and fit into the transposition table.
+    fn evaluate(&self, position: &Position) -> Score {
+        self.eval
+            .evaluate(position)
+            .clamp(-(WIN_THRESHOLD - 1), WIN_THRESHOLD - 1)
+    }
+
+    /// Returns the exact score of a position found in the endgame database. Wins and losses are
+    /// scored like the end of the game `plies` plies later.
+    fn probe_endgame_database(&mut self, position: &Position, ply: usize) -> Option<Score> {
+        let outcome = self.endgame_database.as_ref()?.probe(position)?;
+        self.stats.egdb_hits += 1;
+        Some(match outcome {
+            Outcome::Win(plies) => WIN_SCORE - (ply + plies as usize) as Score,
+            Outcome::Loss(plies) => -(WIN_SCORE - (ply + plies as usize) as Score),
+            Outcome::Draw => DRAW_SCORE,
+        })
+    }
+
+    /// Returns the indices of `possible_moves` in the order they should be searched:
+    /// the transposition table move, captures by size, killer moves, then by history score.
+    fn order_moves(
+        &self,
+        possible_moves: &[Move],
+        tt_move: Option<u8>,
+        ply: usize,
+        color: PieceColor,
+    ) -> Vec<usize> {
+        let killers = self.killers.get(ply);
+        let mut scored = possible_moves
+            .iter()
+            .enumerate()
+            .map(|(i, p_move)| {
+                let priority = if tt_move == Some(i as u8) {
+                    TT_MOVE_PRIORITY
+                } else if p_move.is_capture() {
+                    CAPTURE_PRIORITY
+                        + p_move.captures.len() as u32 * 2
+                        + p_move.captured_kings.count_ones()
+                } else if killers.is_some_and(|killers| {
+                    killers.iter().any(|killer| killer.as_ref() == Some(p_move))
+                }) {
+                    KILLER_PRIORITY
+                } else {
+                    self.history[color as usize][p_move.from() as usize][p_move.to() as usize]
+                };
+                (i, priority)
+            })
+            .collect::<Vec<_>>();
+        scored.sort_by_key(|(_, priority)| Reverse(*priority));
+        scored.into_iter().map(|(i, _)| i).collect()
+    }
+
+    /// Remembers a quiet move that caused a cutoff as killer move and in the history table.
+    fn update_quiet_cutoff(&mut self, p_move: &Move, ply: usize, depth: u8, color: PieceColor) {
+        if let Some(killers) = self.killers.get_mut(ply) {
+            if killers[0].as_ref() != Some(p_move) {
+                killers[1] = killers[0].take();
+                killers[0] = Some(p_move.clone());
+            }
+        }
+        let score = &mut self.history[color as usize][p_move.from() as usize][p_move.to() as usize];
+        *score = score.saturating_add(depth as u32 * depth as u32);
+    }
+
+    /// Win and loss scores count the plies from the root. The transposition table stores them
+    /// counted from the stored position, so that they stay valid when it is reached at another ply.
+    fn value_to_tt(score: Score, ply: usize) -> Score {
+        if score >= WIN_THRESHOLD {
+            score + ply as Score
+        } else if score <= -WIN_THRESHOLD {
+            score - ply as Score
+        } else {
+            score
+        }
+    }
+
+    fn value_from_tt(value: Score, ply: usize) -> Score {
+        if value >= WIN_THRESHOLD {
+            value - ply as Score
+        } else if value <= -WIN_THRESHOLD {
+            value + ply as Score
+        } else {
+            value
+        }
+    }
+
+    /// Checks the search limits and the stop signal. They are only enforced once the first
+    /// iteration completed. Helper threads only stop when the main thread tells them to.
+    fn should_stop(&mut self) -> bool {
+        return_if!(self.stopped, true);
+        if self.id > 0 {
+            if self.stats.nodes.is_multiple_of(TIME_CHECK_INTERVAL) {
+                self.helper_nodes
+                    .fetch_add(TIME_CHECK_INTERVAL, Ordering::Relaxed);
+            }
+            self.stopped = self.handle.is_stopped();
+            return self.stopped;
+        }
+
+        if self.pondering && self.handle.is_ponderhit() {
+            // The time limit starts with the ponderhit
+            self.pondering = false;
+            self.limit_start = Instant::now();
+        }
+        if self.completed_depth > 0 && self.handle.is_stopped() {
+            self.stopped = true;
+        } else if self.completed_depth > 0 && !self.pondering {
+            let nodes = self.stats.nodes + self.helper_nodes.load(Ordering::Relaxed);
+            let nodes_exceeded = self.limits.nodes.is_some_and(|limit| nodes >= limit);
+            // A ponder search can be deeper than the limit when the ponderhit arrives
+            let depth_exceeded = self.completed_depth >= self.limits.depth;
+            let time_exceeded = self.stats.nodes.is_multiple_of(TIME_CHECK_INTERVAL)
+                && self
+                    .limits
+                    .time
+                    .is_some_and(|time| self.limit_start.elapsed() >= time);
+            self.stopped = nodes_exceeded || depth_exceeded || time_exceeded;
+        }
+        self.stopped
+    }
+
+    /// Searches the root moves in the order of `lines` within the window `alpha..beta`.
+    /// The first `multi_pv` moves get exact scores. The remaining moves are searched with a null
+    /// window against the worst of those and only searched again if they turn out to be better.
+    fn search_root(
+        &mut self,
+        position: &mut Position,
+        depth: u8,
+        lines: &[PvLine],
+        alpha: Score,
+        beta: Score,
+    ) -> Iteration {
+        let mut iteration: Vec<PvLine> = Vec::with_capacity(lines.len());
+        let mut line = Vec::new();
+
+        for (n, PvLine { pv, .. }) in lines.iter().enumerate() {
+            let p_move = &pv[0];
+            position.move_piece(p_move);
+            let score = if n == 0 {
+                -self.negamax(position, depth - 1, -beta, -alpha, 1, &mut line)
+            } else if n < self.multi_pv {
+                -self.negamax(position, depth - 1, -INFINITY, INFINITY, 1, &mut line)
+            } else {
+                // The score a move has to beat to be among the reported lines
+                let mut scores = iteration.iter().map(|line| line.score).collect::<Vec<_>>();
+                scores.sort_by_key(|score| Reverse(*score));
+                let threshold = scores[self.multi_pv - 1].max(alpha);
+
+                let score = -self.negamax(
+                    position,
+                    depth - 1,
+                    -threshold - 1,
+                    -threshold,
+                    1,
+                    &mut line,
+                );
+                if score > threshold && score < beta && !self.stopped {
+                    -self.negamax(position, depth - 1, -beta, -threshold, 1, &mut line)
+                } else {
+                    score
+                }
+            };
+            position.unmake_move(p_move);
+            return_if!(self.stopped, Iteration::Stopped);
+            return_if!(n == 0 && score <= alpha, Iteration::FailLow);
+            return_if!(score >= beta, Iteration::FailHigh);
+
+            let mut pv = vec![p_move.clone()];
+            pv.append(&mut line);
+            self.extend_pv(position, &mut pv, depth);
+            iteration.push(PvLine { score, pv });
+        }
+
+        Iteration::Completed(iteration)
+    }
+
+    /// Builds the result of the current search state from the root lines, best first.
+    fn result(&self, lines: Vec<PvLine>) -> SearchResult {
+        // Without a completed iteration the root moves are not scored yet
+        let lines = if self.completed_depth == 0 {
+            lines
+                .into_iter()
+                .map(|line| PvLine {
+                    score: DRAW_SCORE,
+                    ..line
+                })
+                .collect()
+        } else {
+            lines
+        };
+        let (best_move, score, pv) = match lines.first() {
+            Some(line) => (Some(line.pv[0].clone()), line.score, line.pv.clone()),
+            None => (None, -WIN_SCORE, Vec::new()),
+        };
+        let mut result = SearchResult {
+            best_move,
+            score,
+            pv,
+            root_moves: lines
+                .iter()
+                .map(|line| (line.pv[0].clone(), line.score))
+                .collect(),
+            lines: lines.into_iter().take(self.multi_pv).collect(),
+            depth: self.completed_depth,
+            ..Default::default()
+        };
+        let mut stats = self.stats;
+        stats.nodes += self.helper_nodes.load(Ordering::Relaxed);
+        stats.report(&mut result, self.start.elapsed());
+        result
+    }
+
+    /// Extends a line cut short by a transposition table hit or the endgame database with the
+    /// best moves stored in the transposition table or the endgame database, up to `depth` moves.
+    fn extend_pv(&self, position: &mut Position, pv: &mut Vec<Move>, depth: u8) {
+        for p_move in pv.iter() {
+            position.move_piece(p_move);
+        }
+        while pv.len() < depth as usize && position.repetitions() == 0 {
+            let possible_moves = position.possible_moves();
+            let p_move = match self
+                .transposition_table
+                .probe(position.key())
+                .and_then(|entry| entry.best_move)
+                .and_then(|index| possible_moves.get(index as usize).cloned())
+                .or_else(|| self.endgame_database.as_ref()?.best_move(position))
+            {
+                Some(p_move) => p_move,
+                None => break,
+            };
+            position.move_piece(&p_move);
+            pv.push(p_move);
+        }
+        for p_move in pv.iter().rev() {
+            position.unmake_move(p_move);
+        }
+    }
+}
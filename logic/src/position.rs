@@ -0,0 +1,181 @@
+use crate::{
+    board::{Board, Move, MoveError},
+    piece::{self, PieceColor},
+    return_if, zobrist,
+};
+
+/// A board together with the side to move and the move counters.
+#[derive(Clone)]
+pub struct Position {
+    board: Board,
+
+    /// The color of the player to move.
+    turn: PieceColor,
+
+    /// Number of plies since the last capture or man move.
+    halfmove_clock: u16,
+
+    /// Number of plies played since the position was set up.
+    ply: u16,
+
+    /// The state before each move, used to unmake moves and detect repetitions.
+    history: Vec<HistoryEntry>,
+}
+
+#[derive(Clone)]
+struct HistoryEntry {
+    key: u64,
+    halfmove_clock: u16,
+}
+
+impl Position {
+    /* --------------| Constructors |-------------- */
+    pub fn new() -> Self {
+        Self::from_board(Board::new(), piece::WHITE)
+    }
+
+    pub fn from_board(board: Board, turn: PieceColor) -> Self {
+        Self {
+            board,
+            turn,
+            halfmove_clock: 0,
+            ply: 0,
+            history: Vec::new(),
+        }
+    }
+
+    /* --------------| Getters |-------------- */
+    #[inline]
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    #[inline]
+    pub fn turn(&self) -> PieceColor {
+        self.turn
+    }
+
+    #[inline]
+    pub fn halfmove_clock(&self) -> u16 {
+        self.halfmove_clock
+    }
+
+    #[inline]
+    pub fn ply(&self) -> u16 {
+        self.ply
+    }
+
+    /// Returns the Zobrist key of the position, including the side to move.
+    #[inline]
+    pub fn key(&self) -> u64 {
+        if self.turn == piece::WHITE {
+            self.board.key()
+        } else {
+            self.board.key() ^ zobrist::SIDE_KEY
+        }
+    }
+
+    /* --------------| Methods |-------------- */
+    /// Returns how often the current position occurred before with the same side to move.
+    /// Only positions since the last capture or man move can repeat.
+    pub fn repetitions(&self) -> usize {
+        let key = self.key();
+        self.history
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize)
+            .skip(1)
+            .step_by(2)
+            .filter(|entry| entry.key == key)
+            .count()
+    }
+
+    /// Returns all legal moves for the side to move.
+    pub fn possible_moves(&self) -> Vec<Move> {
+        self.board.possible_moves(self.turn)
+    }
+
+    /// Does a move for the side to move after checking that it is legal.
+    pub fn make_move(&mut self, p_move: &Move) -> Result<(), MoveError> {
+        let p_move = self.board.legal_move(p_move, self.turn)?;
+        self.move_piece(&p_move);
+        Ok(())
+    }
+
+    /// Does a move for the side to move.
+    /// ! This method does not check if the move is valid !
+    pub fn move_piece(&mut self, p_move: &Move) {
+        let irreversible = self.is_irreversible(p_move);
+        let key = self.key();
+        self.board.move_piece(p_move);
+        self.advance(key, irreversible);
+    }
+
+    /// Reverts the last move done by `make_move` or `move_piece`.
+    pub fn unmake_move(&mut self, p_move: &Move) {
+        self.board.unmake_move(p_move);
+        self.turn = !self.turn;
+        self.ply -= 1;
+        self.halfmove_clock = self.history.pop().map_or(0, |entry| entry.halfmove_clock);
+    }
+
+    /// Counts the leaf nodes of the move tree `depth` plies deep, to compare the move generation
+    /// with known counts. Positions without moves count as no leaves.
+    pub fn perft(&mut self, depth: u8) -> u64 {
+        return_if!(depth == 0, 1);
+        let possible_moves = self.possible_moves();
+        return_if!(depth == 1, possible_moves.len() as u64);
+
+        let mut nodes = 0;
+        for p_move in &possible_moves {
+            self.move_piece(p_move);
+            nodes += self.perft(depth - 1);
+            self.unmake_move(p_move);
+        }
+        nodes
+    }
+
+    /// Returns the perft count below each legal move, `depth` plies deep counting the move.
+    pub fn divide(&mut self, depth: u8) -> Vec<(Move, u64)> {
+        return_if!(depth == 0, Vec::new());
+        self.possible_moves()
+            .into_iter()
+            .map(|p_move| {
+                self.move_piece(&p_move);
+                let nodes = self.perft(depth - 1);
+                self.unmake_move(&p_move);
+                (p_move, nodes)
+            })
+            .collect()
+    }
+
+    /* --------------| Private methods |-------------- */
+    /// Captures and man moves can never be undone over the board.
+    fn is_irreversible(&self, p_move: &Move) -> bool {
+        p_move.is_capture()
+            || self
+                .board
+                .get_piece(1 << p_move.from())
+                .is_some_and(|piece| piece.piece_type == piece::MAN)
+    }
+
+    fn advance(&mut self, key: u64, irreversible: bool) {
+        self.history.push(HistoryEntry {
+            key,
+            halfmove_clock: self.halfmove_clock,
+        });
+        self.halfmove_clock = if irreversible {
+            0
+        } else {
+            self.halfmove_clock + 1
+        };
+        self.ply += 1;
+        self.turn = !self.turn;
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self::new()
+    }
+}
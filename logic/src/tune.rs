@@ -0,0 +1,254 @@
+use crate::{
+    fen::FenError,
+    piece,
+    player::{Feature, Score, Weights, FEATURE_COUNT, V3},
+    position::Position,
+};
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::Path,
+};
+
+/// Smallest change of a weight tried by the tuner.
+const MIN_STEP: Score = 1;
+/// First change of a weight tried by the tuner. Halved whenever no weight improves anymore.
+const INITIAL_STEP: Score = 8;
+/// Iterations of the golden section search for the scale of the logistic model.
+const SCALE_ITERATIONS: usize = 64;
+
+/// A position labelled with the result of the game it was played in.
+#[derive(Clone)]
+pub struct TrainingPosition {
+    pub position: Position,
+    /// 1 if white won, 0.5 for a draw and 0 if black won.
+    pub result: f64,
+}
+
+/// Reasons for a training file to be rejected. Lines are counted from 1.
+#[derive(Debug)]
+pub enum TrainingDataError {
+    Io(io::Error),
+    InvalidFen { line: usize, error: FenError },
+    InvalidResult { line: usize },
+}
+
+impl Display for TrainingDataError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TrainingDataError::Io(err) => write!(f, "Could not read the training file. {}", err),
+            TrainingDataError::InvalidFen { line, error } => {
+                write!(f, "Invalid position in line {}. {}", line, error)
+            }
+            TrainingDataError::InvalidResult { line } => write!(
+                f,
+                "Invalid result in line {}. Expected 1-0, 0-1, 1/2-1/2 or a number from 0 to 1.",
+                line
+            ),
+        }
+    }
+}
+
+impl Error for TrainingDataError {}
+
+impl From<io::Error> for TrainingDataError {
+    fn from(err: io::Error) -> Self {
+        TrainingDataError::Io(err)
+    }
+}
+
+/// Parses one position per line: a FEN in any supported format followed by the result of the
+/// game from white's point of view, e.g. `W:W21-32:B1-12 1/2-1/2`.
+/// Empty lines and lines starting with '#' are skipped.
+pub fn parse_training_positions(text: &str) -> Result<Vec<TrainingPosition>, TrainingDataError> {
+    let mut positions = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (fen, result) = line
+            .rsplit_once(char::is_whitespace)
+            .ok_or(TrainingDataError::InvalidResult { line: line_number })?;
+        let result =
+            parse_result(result).ok_or(TrainingDataError::InvalidResult { line: line_number })?;
+        let position = Position::from_fen(fen).map_err(|error| TrainingDataError::InvalidFen {
+            line: line_number,
+            error,
+        })?;
+        positions.push(TrainingPosition { position, result });
+    }
+    Ok(positions)
+}
+
+pub fn load_training_positions(
+    path: impl AsRef<Path>,
+) -> Result<Vec<TrainingPosition>, TrainingDataError> {
+    parse_training_positions(&fs::read_to_string(path)?)
+}
+
+/// A position prepared for tuning.
+struct Sample {
+    /// The features from white's point of view.
+    features: [Score; FEATURE_COUNT],
+    result: f64,
+}
+
+/// Fits the weights of the v3 evaluation to game results (Texel's tuning method).
+/// The evaluation is mapped to an expected result with the logistic function
+/// `1 / (1 + e^(-scale * evaluation))` and the weights are changed one at a time as long as the
+/// mean squared error between expected and real results decreases.
+pub struct Tuner {
+    samples: Vec<Sample>,
+    skipped: usize,
+    scale: f64,
+}
+
+impl Tuner {
+    /// Prepares the positions for tuning. Positions where the side to move has to capture are
+    /// skipped, their static evaluation says little about the outcome.
+    pub fn new(positions: &[TrainingPosition]) -> Self {
+        let evaluator = V3::default();
+        let samples = positions
+            .iter()
+            .filter(|sample| {
+                let possible_moves = sample.position.possible_moves();
+                possible_moves
+                    .first()
+                    .is_some_and(|p_move| !p_move.is_capture())
+            })
+            .map(|sample| {
+                let sign = if sample.position.turn() == piece::WHITE {
+                    1
+                } else {
+                    -1
+                };
+                Sample {
+                    features: evaluator
+                        .features(&sample.position)
+                        .map(|value| sign * value),
+                    result: sample.result,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            skipped: positions.len() - samples.len(),
+            samples,
+            scale: 0.01,
+        }
+    }
+
+    /// Number of positions used for tuning.
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Number of positions skipped because of pending captures or no legal moves.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// The scale of the logistic model, see `fit_scale`.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Returns the mean squared error of the expected results with `weights`.
+    pub fn error(&self, weights: &Weights) -> f64 {
+        self.error_with_scale(weights, self.scale)
+    }
+
+    /// Fits the scale of the logistic model to the current weights and returns the error.
+    /// Only the weights are tuned afterwards, the scale stays fixed.
+    pub fn fit_scale(&mut self, weights: &Weights) -> f64 {
+        // Golden section search over the logarithm of the scale
+        let ratio = (5f64.sqrt() - 1.) / 2.;
+        let (mut low, mut high) = (1e-5f64.ln(), 1f64.ln());
+        for _ in 0..SCALE_ITERATIONS {
+            let a = high - ratio * (high - low);
+            let b = low + ratio * (high - low);
+            if self.error_with_scale(weights, a.exp()) < self.error_with_scale(weights, b.exp()) {
+                high = b;
+            } else {
+                low = a;
+            }
+        }
+        self.scale = ((low + high) / 2.).exp();
+        self.error(weights)
+    }
+
+    /// Tunes every weight but the value of a man, which fixes the unit of the evaluation.
+    /// Stops after `max_iterations` passes over all weights or once no change improves the
+    /// error. `progress` receives the iteration and the error after every pass.
+    pub fn tune(
+        &self,
+        mut weights: Weights,
+        max_iterations: usize,
+        mut progress: impl FnMut(usize, f64),
+    ) -> Weights {
+        let mut error = self.error(&weights);
+        let mut step = INITIAL_STEP;
+
+        for iteration in 1..=max_iterations {
+            let mut improved = false;
+            for feature in Feature::ALL.into_iter().skip(1) {
+                for change in [step, -step] {
+                    let mut candidate = weights;
+                    candidate[feature] += change;
+                    let candidate_error = self.error(&candidate);
+                    if candidate_error < error {
+                        weights = candidate;
+                        error = candidate_error;
+                        improved = true;
+                        break;
+                    }
+                }
+            }
+            progress(iteration, error);
+
+            if !improved {
+                if step == MIN_STEP {
+                    break;
+                }
+                step = (step / 2).max(MIN_STEP);
+            }
+        }
+        weights
+    }
+
+    /* --------------| Private methods |-------------- */
+    fn error_with_scale(&self, weights: &Weights, scale: f64) -> f64 {
+        let total = self
+            .samples
+            .iter()
+            .map(|sample| {
+                let evaluation = sample
+                    .features
+                    .iter()
+                    .zip(weights.values())
+                    .map(|(feature, weight)| feature * weight)
+                    .sum::<Score>();
+                let expected = 1. / (1. + (-scale * evaluation as f64).exp());
+                (sample.result - expected).powi(2)
+            })
+            .sum::<f64>();
+        total / self.samples.len().max(1) as f64
+    }
+}
+
+/// Parses a game result from white's point of view.
+fn parse_result(result: &str) -> Option<f64> {
+    match result {
+        "1-0" | "2-0" => Some(1.),
+        "0-1" | "0-2" => Some(0.),
+        "1/2-1/2" | "1-1" => Some(0.5),
+        _ => result
+            .parse::<f64>()
+            .ok()
+            .filter(|result| (0. ..=1.).contains(result)),
+    }
+}
@@ -32,3 +32,37 @@ pub fn _chess_to_checkers_id(id: &u8) -> u8 {
     let col = *id % 8;
     row * 4 + col / 2
 }
+
+/// A xorshift64* pseudo random number generator, good enough to pick between moves.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    /// The state must never be zero, so a zero seed is replaced.
+    pub fn new(seed: u64) -> Self {
+        Self(if seed == 0 {
+            0x9E37_79B9_7F4A_7C15
+        } else {
+            seed
+        })
+    }
+
+    /// Seeds the generator with the current time.
+    pub fn from_time() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+        Self::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a number from 0 to `bound - 1`. `bound` must not be zero.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
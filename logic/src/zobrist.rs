@@ -0,0 +1,50 @@
+use crate::piece::{self, Piece};
+
+const SEED: u64 = 0x2545_F491_4F6C_DD1D;
+
+/// Random keys for every piece kind on every square, indexed by `piece_index`.
+const PIECE_KEYS: [[u64; 64]; 4] = generate_piece_keys();
+
+/// Key toggled when black is to move.
+pub const SIDE_KEY: u64 = splitmix64(SEED ^ 0xFFFF_FFFF_FFFF_FFFF).1;
+
+#[inline]
+pub fn piece_key(piece: &Piece, id: u8) -> u64 {
+    PIECE_KEYS[piece_index(piece)][id as usize]
+}
+
+#[inline]
+fn piece_index(piece: &Piece) -> usize {
+    (if piece.color == piece::WHITE { 0 } else { 2 })
+        + (if piece.piece_type == piece::KING {
+            1
+        } else {
+            0
+        })
+}
+
+/// Returns the next state and the next random number of a SplitMix64 generator.
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (state, z ^ (z >> 31))
+}
+
+const fn generate_piece_keys() -> [[u64; 64]; 4] {
+    let mut keys = [[0; 64]; 4];
+    let mut state = SEED;
+    let mut kind = 0;
+    while kind < 4 {
+        let mut id = 0;
+        while id < 64 {
+            let (next, key) = splitmix64(state);
+            state = next;
+            keys[kind][id] = key;
+            id += 1;
+        }
+        kind += 1;
+    }
+    keys
+}
@@ -0,0 +1,65 @@
+use logic::{play_match, Ballot, BallotError, FenFormat, MinimaxPlayer, Player, V1};
+
+const DECK: &str = "\
+# A small deck
+11-15 23-19 8-11
+9-13 22-17 13x22   # an exchange on the third move
+
+12-16 24-20 8-12
+";
+
+#[test]
+fn deck_keeps_its_order() {
+    let ballots = Ballot::parse_deck(DECK).unwrap();
+    let notations = ballots.iter().map(Ballot::to_string).collect::<Vec<_>>();
+    assert_eq!(
+        notations,
+        ["11-15 23-19 8-11", "9-13 22-17 13x22", "12-16 24-20 8-12"]
+    );
+}
+
+#[test]
+fn ballot_position_has_white_to_move() {
+    let ballot = Ballot::parse("11-15 23-19 8-11").unwrap();
+    assert_eq!(ballot.moves().len(), 3);
+    assert_eq!(
+        ballot.position().to_fen(FenFormat::Pdn),
+        "W:W19,21,22,24,25,26,27,28,29,30,31,32:B1,2,3,4,5,6,7,9,10,11,12,15"
+    );
+    assert_eq!(ballot.board().key(), ballot.position().board().key());
+}
+
+#[test]
+fn invalid_ballots_are_rejected() {
+    assert!(matches!(
+        Ballot::parse("11-15 23-19"),
+        Err(BallotError::MoveCount(2))
+    ));
+    assert!(matches!(
+        Ballot::parse("11-15 23-19 8-12"),
+        Err(BallotError::IllegalMove(notation)) if notation == "8-12"
+    ));
+    assert!(matches!(
+        Ballot::parse_deck("11-15 23-19 8-11\n11-15 23-18 8-12"),
+        Err(BallotError::InvalidLine { line: 2, .. })
+    ));
+    assert!(matches!(
+        Ballot::parse_deck("11-15 23-19 8-11\n# again\n11-15  23-19  8-11"),
+        Err(BallotError::Duplicate { line: 3 })
+    ));
+}
+
+#[test]
+fn match_plays_both_colors() {
+    let ballots = Ballot::parse_deck(DECK).unwrap();
+    let player = || -> Box<dyn Player> { Box::new(MinimaxPlayer::new(2, V1)) };
+    let mut reported = 0;
+    let result = play_match(&ballots[..1], player, player, |_, ballot_result| {
+        reported += 1;
+        assert_eq!(ballot_result.games(), 2);
+    });
+    assert_eq!(reported, 1);
+    assert_eq!(result.games(), 2);
+    // The same deterministic player on both sides scores the same with either color
+    assert_eq!(result.wins, result.losses);
+}
@@ -0,0 +1,35 @@
+use logic::{square_to_id, Move, MoveError, Position};
+
+fn path(squares: &[u8]) -> Vec<u8> {
+    squares.iter().map(|s| square_to_id(*s).unwrap()).collect()
+}
+
+#[test]
+fn moves_are_matched_by_their_path() {
+    // White jumps the black king on 7 and is crowned on 2
+    let mut position = Position::from_fen("W:W11:BK7").unwrap();
+    let incomplete = Move {
+        path: path(&[11, 2]),
+        captures: Vec::new(),
+        captured_kings: 0,
+        promotion: false,
+    };
+    position.make_move(&incomplete).unwrap();
+
+    let after = Position::from_fen("B:WK2:B").unwrap();
+    assert_eq!(position.board().to_string(), after.board().to_string());
+    assert_eq!(position.halfmove_clock(), 0);
+}
+
+#[test]
+fn moves_with_another_path_are_illegal() {
+    let mut position = Position::from_fen("W:W11:BK7").unwrap();
+    // The capture is mandatory
+    let quiet = Move {
+        path: path(&[11, 8]),
+        captures: Vec::new(),
+        captured_kings: 0,
+        promotion: false,
+    };
+    assert_eq!(position.make_move(&quiet), Err(MoveError::Illegal));
+}
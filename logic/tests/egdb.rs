@@ -0,0 +1,76 @@
+use logic::{EndgameDatabase, Material, Table};
+use std::{fs, path::PathBuf};
+
+/// A directory of its own for every test, removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("egdb-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn tables_match_a_brute_force_search() {
+    let dir = TempDir::new("validate");
+    let database = EndgameDatabase::generate(&dir.0, 3, |_| {}).unwrap();
+    assert_eq!(database.max_pieces(), 3);
+
+    let report = database.validate(3, 15, |_| {});
+    assert!(report.positions > 0);
+    assert!(
+        report.mismatches.is_empty(),
+        "{} of {} positions do not match, e.g. {}",
+        report.mismatches.len(),
+        report.positions,
+        report.mismatches[0].position.to_fen(logic::FenFormat::Pdn)
+    );
+
+    // The tables written by the generator are found again
+    let opened = EndgameDatabase::open(&dir.0).unwrap();
+    assert_eq!(opened.max_pieces(), 3);
+    assert_eq!(
+        opened.validate(2, 15, |_| {}).mismatches.len(),
+        0,
+        "opened tables differ from the generated ones"
+    );
+}
+
+#[test]
+fn tables_survive_saving_and_loading() {
+    let dir = TempDir::new("round-trip");
+    let database = EndgameDatabase::generate(&dir.0, 3, |_| {}).unwrap();
+    // Two kings against a man: wins, losses, draws and invalid placements
+    let material = Material::new(0, 2, 1, 0);
+    let table = database.table(material).unwrap().unwrap();
+
+    let copy_dir = TempDir::new("round-trip-copy");
+    let path = table.save(&copy_dir.0).unwrap();
+    let loaded = Table::load(&path).unwrap();
+
+    assert_eq!(loaded.material(), material);
+    assert_eq!(loaded.len(), table.len());
+    for index in 0..table.len() {
+        assert_eq!(loaded.get(index), table.get(index), "index {}", index);
+    }
+    let (stats, loaded_stats) = (table.stats(), loaded.stats());
+    assert_eq!(
+        (stats.wins, stats.losses, stats.draws, stats.longest),
+        (
+            loaded_stats.wins,
+            loaded_stats.losses,
+            loaded_stats.draws,
+            loaded_stats.longest
+        )
+    );
+    assert!(stats.wins > 0 && stats.draws > 0);
+}
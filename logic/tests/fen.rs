@@ -0,0 +1,67 @@
+use logic::{FenError, FenFormat, Position};
+
+/// Positions with men and kings of both colors and either side to move.
+const POSITIONS: [&str; 5] = [
+    "W:W21-32:B1-12",
+    "B:W18,19,K26,27,30:B1,K14,15",
+    "W:WK1,K4,K29,K32:BK10,K11,K14,K15,K18,K19,K22,K23",
+    "B:WK6,7,15,23,24:BK28,K32,19,20",
+    "W:W:B5",
+];
+
+#[test]
+fn rows_and_pdn_round_trip() {
+    for fen in POSITIONS {
+        let position = Position::from_fen(fen).unwrap();
+        let rows = position.to_fen(FenFormat::Rows);
+        let pdn = position.to_fen(FenFormat::Pdn);
+
+        let from_rows = Position::from_fen(&rows).unwrap();
+        let from_pdn = Position::from_fen(&pdn).unwrap();
+        assert_eq!(from_rows.to_fen(FenFormat::Pdn), pdn, "{}", fen);
+        assert_eq!(from_pdn.to_fen(FenFormat::Rows), rows, "{}", fen);
+        assert_eq!(from_rows.key(), position.key(), "{}", fen);
+        assert_eq!(from_pdn.key(), position.key(), "{}", fen);
+    }
+}
+
+#[test]
+fn occupied_squares_are_rejected() {
+    for (fen, index) in [
+        ("W:W21,21:B5", 6),
+        ("W:W21,K21:B5", 6),
+        ("W:W21:B5,21", 9),
+        ("W:W20-24,22:B5", 9),
+    ] {
+        assert_eq!(
+            Position::from_fen(fen).err(),
+            Some(FenError::OccupiedSquare { index }),
+            "{}",
+            fen
+        );
+    }
+}
+
+#[test]
+fn reversed_ranges_are_rejected() {
+    for (fen, index) in [("W:W21:B5-3", 7), ("W:WK30-29:B5", 3)] {
+        assert_eq!(
+            Position::from_fen(fen).err(),
+            Some(FenError::InvalidRange { index }),
+            "{}",
+            fen
+        );
+    }
+}
+
+#[test]
+fn squares_are_plain_numbers() {
+    for (fen, index) in [("W:W+5:B1", 3), ("W:WK+5:B1", 4), ("W:W21:B1-+5", 9)] {
+        assert_eq!(
+            Position::from_fen(fen).err(),
+            Some(FenError::InvalidSquare { index }),
+            "{}",
+            fen
+        );
+    }
+}
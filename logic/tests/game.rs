@@ -0,0 +1,117 @@
+use logic::{DrawReason, Game, GameState, Move, Player, Position};
+
+/// Plays its moves in a loop, given in standard notation.
+struct ScriptedPlayer {
+    moves: Vec<&'static str>,
+    next: usize,
+}
+
+impl ScriptedPlayer {
+    fn new(moves: &[&'static str]) -> Box<Self> {
+        Box::new(Self {
+            moves: moves.to_vec(),
+            next: 0,
+        })
+    }
+}
+
+impl Player for ScriptedPlayer {
+    fn init(&mut self, _color: bool) {}
+
+    fn get_move(&mut self, _position: &Position, possible_moves: &[Move]) -> Move {
+        let notation = self.moves[self.next % self.moves.len()];
+        self.next += 1;
+        possible_moves
+            .iter()
+            .find(|p_move| p_move.to_string() == notation)
+            .unwrap_or_else(|| panic!("{} is not legal", notation))
+            .clone()
+    }
+}
+
+/// A white and a black king in their corners, shuffling back and forth.
+const KINGS: &str = "W:WK29:BK4";
+
+fn play(position: &mut Position, moves: &[&str]) {
+    for notation in moves {
+        let p_move = position
+            .possible_moves()
+            .into_iter()
+            .find(|p_move| p_move.to_string() == *notation)
+            .unwrap();
+        position.make_move(&p_move).unwrap();
+    }
+}
+
+#[test]
+fn repetitions_count_earlier_occurrences() {
+    let mut position = Position::from_fen(KINGS).unwrap();
+    assert_eq!(position.repetitions(), 0);
+    play(&mut position, &["29-25", "4-8"]);
+    assert_eq!(position.repetitions(), 0);
+    play(&mut position, &["25-29"]);
+    assert_eq!(position.repetitions(), 0);
+    play(&mut position, &["8-4"]);
+    assert_eq!(position.repetitions(), 1);
+    play(&mut position, &["29-25"]);
+    assert_eq!(position.repetitions(), 1);
+    play(&mut position, &["4-8", "25-29", "8-4"]);
+    assert_eq!(position.repetitions(), 2);
+}
+
+#[test]
+fn repetitions_start_again_after_a_man_move() {
+    let mut position = Position::from_fen("W:WK29,32:BK4").unwrap();
+    play(&mut position, &["29-25", "4-8", "25-29", "8-4"]);
+    assert_eq!(position.repetitions(), 1);
+    play(&mut position, &["32-27"]);
+    assert_eq!(position.halfmove_clock(), 0);
+    assert_eq!(position.repetitions(), 0);
+    // The position right after the man move comes back
+    play(&mut position, &["4-8", "29-25", "8-4", "25-29"]);
+    assert_eq!(position.halfmove_clock(), 4);
+    assert_eq!(position.repetitions(), 1);
+}
+
+#[test]
+fn third_repetition_draws() {
+    let mut game = Game::from_position(
+        Position::from_fen(KINGS).unwrap(),
+        ScriptedPlayer::new(&["29-25", "25-29"]),
+        ScriptedPlayer::new(&["4-8", "8-4"]),
+    );
+    game.no_progress_moves = None;
+    game.play();
+    assert_eq!(game.state, GameState::Draw(DrawReason::Repetition));
+    assert_eq!(game.position.ply(), 8);
+}
+
+#[test]
+fn no_progress_rule_draws_after_exactly_2n_plies() {
+    // Every king move is reversible, the rule ends the game before the third repetition
+    for moves in 1..=3 {
+        let mut game = Game::from_position(
+            Position::from_fen(KINGS).unwrap(),
+            ScriptedPlayer::new(&["29-25", "25-29"]),
+            ScriptedPlayer::new(&["4-8", "8-4"]),
+        );
+        game.no_progress_moves = Some(moves);
+        game.play();
+        assert_eq!(game.state, GameState::Draw(DrawReason::NoProgress));
+        assert_eq!(game.position.ply(), moves * 2, "{} moves", moves);
+    }
+}
+
+#[test]
+fn man_moves_reset_the_no_progress_count() {
+    let mut game = Game::from_position(
+        Position::from_fen("W:WK29,32:BK4").unwrap(),
+        ScriptedPlayer::new(&["29-25", "32-27", "25-29", "29-25"]),
+        ScriptedPlayer::new(&["4-8", "8-4"]),
+    );
+    game.no_progress_moves = Some(2);
+    game.play();
+    assert_eq!(game.state, GameState::Draw(DrawReason::NoProgress));
+    // The man move in ply 3 restarts the count of 4 reversible plies
+    assert_eq!(game.position.ply(), 3 + 4);
+}
@@ -0,0 +1,70 @@
+use logic::{parse_pdn, FenFormat, PdnError, PdnGame};
+
+/// A white king with two capture routes from 17 back to 17 and two from 17 to 1.
+const LOOPS: &str = "W:WK17:B6,7,14,15,21,22,23";
+
+fn moves(game: &PdnGame) -> Vec<String> {
+    game.moves.iter().map(|p_move| p_move.to_string()).collect()
+}
+
+#[test]
+fn move_numbers_are_skipped() {
+    let games = parse_pdn("1. 11-15 23-19 2.8-11 22-17 3... 4-8 1-0").unwrap();
+    assert_eq!(games.len(), 1);
+    assert_eq!(moves(&games[0]), ["11-15", "23-19", "8-11", "22-17", "4-8"]);
+}
+
+#[test]
+fn comments_and_variations_are_skipped() {
+    let text = "
+        [Event \"Test\"]
+        1. 11-15 {the Old Faithful} 23-19 (23-18 (21-17) 12-16) ; a line comment 22-18
+        2. 8-11! 22-17? *";
+    let games = parse_pdn(text).unwrap();
+    assert_eq!(games.len(), 1);
+    assert_eq!(games[0].tag("Event"), Some("Test"));
+    assert_eq!(moves(&games[0]), ["11-15", "23-19", "8-11", "22-17"]);
+}
+
+#[test]
+fn games_start_from_the_fen_tag() {
+    let text = "
+        [FEN \"W:WK17:B6,7,14,15,21,22,23\"]
+        1. 17x10x1 *
+        [Event \"Second\"]
+        1. 9-13 0-1";
+    let games = parse_pdn(text).unwrap();
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].start.to_fen(FenFormat::Pdn), LOOPS);
+    assert_eq!(moves(&games[0]), ["17x10x1"]);
+    assert_eq!(games[1].tag("Event"), Some("Second"));
+    assert_eq!(moves(&games[1]), ["9-13"]);
+}
+
+#[test]
+fn shortened_captures_need_a_single_route() {
+    // 17x1 jumps over 14 and 6 or around the loop through 26, 19 and 10
+    let text = format!("[FEN \"{}\"] 1. 17x1 *", LOOPS);
+    match parse_pdn(&text) {
+        Err(PdnError::AmbiguousMove { game, notation }) => {
+            assert_eq!((game, notation.as_str()), (1, "17x1"))
+        }
+        Err(err) => panic!("unexpected error: {}", err),
+        Ok(_) => panic!("the ambiguous capture was accepted"),
+    }
+
+    // A single route may be shortened
+    let games = parse_pdn("[FEN \"W:W22:B18,11\"] 1. 22x8 *").unwrap();
+    assert_eq!(moves(&games[0]), ["22x15x8"]);
+}
+
+#[test]
+fn illegal_moves_name_their_game() {
+    match parse_pdn("1. 11-15 * 1. 11-17 *") {
+        Err(PdnError::IllegalMove { game, notation }) => {
+            assert_eq!((game, notation.as_str()), (2, "11-17"))
+        }
+        Err(err) => panic!("unexpected error: {}", err),
+        Ok(_) => panic!("the illegal move was accepted"),
+    }
+}
@@ -0,0 +1,130 @@
+use logic::{FenFormat, Position};
+
+/// Perft counts of the initial position with black to move, as in standard notation.
+const INITIAL_COUNTS: [u64; 8] = [7, 49, 302, 1469, 7361, 36768, 179740, 845931];
+
+/// Positions full of captures with their perft counts from depth 1, checked against an
+/// independent move generator.
+const CAPTURE_POSITIONS: [(&str, &[u64]); 8] = [
+    // A king with branching multi-jumps in every direction
+    ("W:WK17:B6,7,14,15,21,22,23", &[6, 38, 88, 522, 1564, 9715]),
+    // Two kings surrounded by men
+    (
+        "W:WK10,K28:B6,7,14,15,22,23,24",
+        &[7, 35, 75, 377, 1551, 9275, 37852],
+    ),
+    // A man crowned by a capture ends the move, although the new king could jump on
+    ("W:W11:B6,7,24", &[1, 4, 6, 19, 58, 181]),
+    // Men and a king crowded in the center
+    (
+        "W:W9,10,11,12,K21:B5,6,7,8,14,15,16,22,23,24",
+        &[6, 30, 85, 843, 3000, 23411, 110239],
+    ),
+    // Black to move with a king among white men
+    (
+        "B:W10,11,18,19,26,27:B1,K14,15",
+        &[8, 33, 115, 418, 1852, 7216, 33845],
+    ),
+    (
+        "B:W10,11,18,19,26,27,K6:B1,2,15",
+        &[3, 26, 50, 302, 932, 4476],
+    ),
+    // Kings only, with captures for both sides
+    (
+        "W:WK1,K4,K29,K32:BK10,K11,K14,K15,K18,K19,K22,K23",
+        &[6, 32, 146, 972, 4788, 38736, 201944],
+    ),
+    (
+        "B:WK6,7,15,23,24:BK28,K32,19,20",
+        &[2, 10, 23, 143, 747, 4921, 27155],
+    ),
+];
+
+fn initial_position() -> Position {
+    Position::from_fen("B:W21-32:B1-12").unwrap()
+}
+
+#[test]
+fn initial_position_counts() {
+    let mut position = initial_position();
+    for (depth, count) in INITIAL_COUNTS.iter().enumerate() {
+        assert_eq!(
+            position.perft(depth as u8 + 1),
+            *count,
+            "depth {}",
+            depth + 1
+        );
+    }
+}
+
+#[test]
+fn initial_position_with_white_to_move() {
+    let mut position = Position::new();
+    for (depth, count) in INITIAL_COUNTS.iter().enumerate().take(6) {
+        assert_eq!(
+            position.perft(depth as u8 + 1),
+            *count,
+            "depth {}",
+            depth + 1
+        );
+    }
+}
+
+#[test]
+fn capture_position_counts() {
+    for (fen, counts) in CAPTURE_POSITIONS {
+        let mut position = Position::from_fen(fen).unwrap();
+        for (depth, count) in counts.iter().enumerate() {
+            assert_eq!(
+                position.perft(depth as u8 + 1),
+                *count,
+                "{} at depth {}",
+                fen,
+                depth + 1
+            );
+        }
+    }
+}
+
+#[test]
+fn divide_adds_up_to_perft() {
+    for (fen, counts) in CAPTURE_POSITIONS {
+        let mut position = Position::from_fen(fen).unwrap();
+        let divide = position.divide(4);
+        assert_eq!(divide.len() as u64, counts[0], "{}", fen);
+        assert_eq!(
+            divide.iter().map(|(_, nodes)| nodes).sum::<u64>(),
+            position.perft(4),
+            "{}",
+            fen
+        );
+    }
+}
+
+#[test]
+fn crowning_ends_the_capture() {
+    let mut position = Position::from_fen("W:W11:B6,7,24").unwrap();
+    let divide = position.divide(1);
+    assert_eq!(divide.len(), 1);
+    assert_eq!(divide[0].0.to_string(), "11x2");
+    assert!(divide[0].0.promotion);
+}
+
+#[test]
+fn perft_restores_the_position() {
+    let mut positions = vec![initial_position()];
+    positions.extend(
+        CAPTURE_POSITIONS
+            .iter()
+            .map(|(fen, _)| Position::from_fen(fen).unwrap()),
+    );
+    for mut position in positions {
+        let fen = position.to_fen(FenFormat::Pdn);
+        let key = position.key();
+        position.perft(5);
+        assert_eq!(position.to_fen(FenFormat::Pdn), fen);
+        assert_eq!(position.key(), key);
+        assert_eq!(position.ply(), 0);
+        assert_eq!(position.halfmove_clock(), 0);
+    }
+}
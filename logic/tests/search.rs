@@ -0,0 +1,106 @@
+use logic::{
+    evaluator, win_distance, MinimaxPlayer, Position, SearchLimits, WinDistance, DRAW_SCORE, V2,
+    WIN_SCORE,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+#[test]
+fn root_without_moves_is_lost() {
+    // White's only man is blocked by the black man in front of it
+    let position = Position::from_fen("W:W5:B1").unwrap();
+    assert!(position.possible_moves().is_empty());
+
+    let result = MinimaxPlayer::new(3, V2).analyse(&position);
+    assert_eq!(result.best_move, None);
+    assert_eq!(result.score, -WIN_SCORE);
+    assert_eq!(win_distance(result.score), Some(WinDistance::Loss(0)));
+}
+
+#[test]
+fn win_and_loss_distances_keep_their_sign() {
+    assert_eq!(win_distance(WIN_SCORE), Some(WinDistance::Win(0)));
+    assert_eq!(win_distance(WIN_SCORE - 3), Some(WinDistance::Win(3)));
+    assert_eq!(win_distance(-WIN_SCORE + 4), Some(WinDistance::Loss(4)));
+    assert_eq!(win_distance(DRAW_SCORE), None);
+    assert_eq!(win_distance(-WIN_SCORE - 1), None);
+}
+
+#[test]
+fn search_without_iterations_is_not_scored() {
+    let result = MinimaxPlayer::with_limits(
+        SearchLimits {
+            depth: 0,
+            time: None,
+            nodes: None,
+        },
+        V2,
+    )
+    .analyse(&Position::new());
+    assert_eq!(result.depth, 0);
+    assert!(result.best_move.is_some());
+    assert_eq!(result.score, DRAW_SCORE);
+    assert_eq!(win_distance(result.score), None);
+}
+
+#[test]
+fn single_thread_search_is_deterministic() {
+    let position =
+        Position::from_fen("W:W18,21,22,24,25,26,27,28,29,30,31,32:B1,2,3,4,5,6,7,8,9,10,12,15")
+            .unwrap();
+    let search = || {
+        MinimaxPlayer::with_evaluator(SearchLimits::depth(8), evaluator("v3").unwrap())
+            .analyse(&position)
+    };
+    let (first, second) = (search(), search());
+
+    assert!(first.best_move.is_some());
+    assert_eq!(first.best_move, second.best_move);
+    assert_eq!(first.score, second.score);
+    assert_eq!(first.pv, second.pv);
+    assert_eq!(first.nodes, second.nodes);
+}
+
+#[test]
+fn stop_ends_a_running_search() {
+    let mut player = MinimaxPlayer::with_limits(SearchLimits::time(Duration::from_secs(600)), V2);
+    let handle = player.handle();
+    let stopper = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(200));
+        handle.stop();
+    });
+
+    let start = Instant::now();
+    let result = player.analyse(&Position::new());
+    stopper.join().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert!(result.depth >= 1);
+    assert!(result.best_move.is_some());
+}
+
+#[test]
+fn ponder_returns_only_after_a_ponderhit() {
+    let mut player = MinimaxPlayer::new(2, V2);
+    let handle = player.handle();
+    let ponderhit = Arc::new(AtomicBool::new(false));
+    let ponderer = {
+        let ponderhit = Arc::clone(&ponderhit);
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            ponderhit.store(true, Ordering::SeqCst);
+            handle.ponderhit();
+        })
+    };
+
+    let result = player.ponder(&Position::new());
+    assert!(ponderhit.load(Ordering::SeqCst));
+    ponderer.join().unwrap();
+    assert!(result.depth >= 1);
+    assert!(result.best_move.is_some());
+}